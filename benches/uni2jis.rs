@@ -0,0 +1,108 @@
+//! Compares lookup cost of `Unicode2JisTable` formats on host.
+//!
+//! Run with `cargo bench --bench uni2jis --target <host triple>`.
+
+use picolony::string::{Unicode2Jis0213Table, UNI2JIS0213_DATA, UNI2JIS_DATA};
+
+use std::{hint::black_box, time::Instant};
+
+/// Rounds of querying whole BMP.
+const ROUNDS: usize = 20;
+
+/// Chain lengths to build blocked tables with.
+const BLOCKED_CHAIN_LENGTHS: [u16; 4] = [8, 16, 32, 256];
+
+fn main() {
+    let queries: Vec<char> = (0..0x10000).filter_map(char::from_u32).collect();
+    let kana_kanji: Vec<char> = queries
+        .iter()
+        .copied()
+        .filter(|c| ('\u{3040}'..='\u{9FFF}').contains(c))
+        .collect();
+
+    println!("{:<28} {:>14} {:>14}", "table", "whole BMP", "kana/kanji");
+    for (name, bytes) in [("uni2jis", UNI2JIS_DATA), ("uni2jis0213", UNI2JIS0213_DATA)] {
+        let elements = read_elements(bytes);
+        bench_table(
+            &format!("{name} (as shipped)"),
+            bytes,
+            bytes,
+            &queries,
+            &kana_kanji,
+        );
+        for chain_length in BLOCKED_CHAIN_LENGTHS {
+            let blocked = build_blocked(&elements, chain_length);
+            let label = format!("{name} (v3, chain {chain_length})");
+            bench_table(&label, &blocked, bytes, &queries, &kana_kanji);
+        }
+    }
+}
+
+/// Prints average lookup time of the table after checking it against the original.
+fn bench_table(label: &str, bytes: &[u8], original: &[u8], queries: &[char], kana_kanji: &[char]) {
+    // Charset marker does not affect lookup, so any table is read as JIS X 0213.
+    let table = Unicode2Jis0213Table::new(bytes).expect("Invalid table");
+    let original = Unicode2Jis0213Table::new(original).expect("Invalid table");
+    assert!(queries.iter().all(|&c| table.query(c) == original.query(c)));
+
+    let average = |chars: &[char]| {
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            for &c in chars {
+                black_box(table.query(black_box(c)));
+            }
+        }
+        let nanos = start.elapsed().as_secs_f64() * 1e9 / (ROUNDS * chars.len()) as f64;
+        format!("{nanos:.2} ns")
+    };
+    println!(
+        "{:<28} {:>14} {:>14}",
+        label,
+        average(queries),
+        average(kana_kanji)
+    );
+}
+
+/// Reads all `(codepoint, ku, ten)` from version 1 or 2 table.
+fn read_elements(bytes: &[u8]) -> Vec<(u32, u8, u8)> {
+    let word = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]) as usize;
+    let (header_size, chain_length, elements_count, astral_count) = match word(0) {
+        0 => (10, word(4), word(6), word(8)),
+        chain_length => (4, chain_length, word(2), 0),
+    };
+
+    let elements_start = header_size + (0x10000 / chain_length) * 2;
+    let astral_start = elements_start + elements_count * 4;
+    let bmp = bytes[elements_start..astral_start]
+        .chunks_exact(4)
+        .map(|e| (u16::from_le_bytes([e[0], e[1]]) as u32, e[2], e[3]));
+    let astral = bytes[astral_start..(astral_start + astral_count * 5)]
+        .chunks_exact(5)
+        .map(|e| (u32::from_le_bytes([e[0], e[1], e[2], 0]), e[3], e[4]));
+    bmp.chain(astral).collect()
+}
+
+/// Builds version 3 table from sorted elements.
+fn build_blocked(elements: &[(u32, u8, u8)], chain_length: u16) -> Vec<u8> {
+    let (bmp, astral): (Vec<(u32, u8, u8)>, Vec<_>) = elements.iter().partition(|e| e.0 < 0x10000);
+
+    let mut bytes = vec![];
+    for word in [0, 3, chain_length, bmp.len() as u16, astral.len() as u16] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    for chain in 0..=(0x10000 / chain_length as u32) {
+        let index = bmp.partition_point(|e| e.0 < chain * chain_length as u32);
+        bytes.extend_from_slice(&(index as u16).to_le_bytes());
+    }
+    for &(codepoint, _, _) in &bmp {
+        bytes.extend_from_slice(&(codepoint as u16).to_le_bytes());
+    }
+    for &(_, ku, ten) in &bmp {
+        bytes.extend_from_slice(&[ku, ten]);
+    }
+    for &(codepoint, ku, ten) in &astral {
+        bytes.extend_from_slice(&codepoint.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&[ku, ten]);
+    }
+    bytes
+}
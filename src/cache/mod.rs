@@ -0,0 +1,305 @@
+pub mod strategy;
+
+use crate::cache::strategy::{EvictionStrategy, Lru};
+
+use core::{fmt::Debug, hash::Hash, mem::MaybeUninit};
+
+use heapless::{FnvIndexMap, Vec};
+
+/// Fixed-size cache map.
+/// When full, an entry chosen by eviction strategy `E` will be evicted.
+pub struct SimpleCacheMap<K, V, const SIZE: usize, E = Lru>
+where
+    E: EvictionStrategy,
+{
+    index_map: FnvIndexMap<K, usize, SIZE>,
+
+    // Slot is initialized if and only if `index_map` points to it.
+    data: [MaybeUninit<(K, V)>; SIZE],
+    filled: usize,
+    vacant: Vec<usize, SIZE>,
+    slots: [E::Slot; SIZE],
+    state: E::State,
+    pinned: [bool; SIZE],
+    pinned_count: usize,
+    stats: CacheStats,
+}
+
+/// Counters of `SimpleCacheMap` operations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups which found the entry.
+    pub hits: u32,
+
+    /// Lookups which did not find the entry.
+    pub misses: u32,
+
+    /// Entries evicted to make room for new ones.
+    pub evictions: u32,
+
+    /// Misses in `get_or_else` whose `generate_value()` returned `None`.
+    pub generate_failures: u32,
+}
+
+impl<K, V, const SIZE: usize, E> SimpleCacheMap<K, V, SIZE, E>
+where
+    K: Debug + Clone + Eq + Hash,
+    E: EvictionStrategy,
+{
+    /// Creates new instance.
+    pub const fn new() -> SimpleCacheMap<K, V, SIZE, E> {
+        SimpleCacheMap {
+            index_map: FnvIndexMap::new(),
+            // An array of `MaybeUninit` requires no initialization.
+            data: unsafe { MaybeUninit::uninit().assume_init() },
+            filled: 0,
+            vacant: Vec::new(),
+            slots: [E::INITIAL_SLOT; SIZE],
+            state: E::INITIAL_STATE,
+            pinned: [false; SIZE],
+            pinned_count: 0,
+            stats: CacheStats {
+                hits: 0,
+                misses: 0,
+                evictions: 0,
+                generate_failures: 0,
+            },
+        }
+    }
+
+    /// Returns operation counters since creation or last reset.
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    /// Resets operation counters.
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Returns the number of cached entries.
+    pub fn len(&self) -> usize {
+        self.index_map.len()
+    }
+
+    /// Whether no entries are cached.
+    pub fn is_empty(&self) -> bool {
+        self.index_map.is_empty()
+    }
+
+    /// Returns the maximum number of entries.
+    pub fn capacity(&self) -> usize {
+        SIZE
+    }
+
+    /// Whether the key is cached.
+    /// Unlike `get`, this does not count as use of the entry.
+    pub fn contains_key(&self, key: K) -> bool {
+        self.index_map.contains_key(&key)
+    }
+
+    /// Iterates over cached entries in unspecified order.
+    /// Unlike `get`, this does not count as use of the entries.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.index_map
+            .iter()
+            .map(|(key, &index)| (key, self.value(index)))
+    }
+
+    /// Gets cached value.
+    pub fn get(&mut self, key: K) -> Option<&V> {
+        let Some(&index) = self.index_map.get(&key) else {
+            self.stats.misses = self.stats.misses.saturating_add(1);
+            return None;
+        };
+        self.stats.hits = self.stats.hits.saturating_add(1);
+        self.touch(index);
+        Some(self.value(index))
+    }
+
+    /// Puts new value to cache.
+    /// If the cache is full, an entry will be evicted.
+    pub fn put(&mut self, key: K, value: V) -> &V {
+        if let Some(&index) = self.index_map.get(&key) {
+            self.touch(index);
+            // Old value is dropped by assignment.
+            unsafe {
+                self.data[index].assume_init_mut().1 = value;
+            }
+            return self.value(index);
+        }
+
+        let index = if let Some(index) = self.vacant.pop() {
+            index
+        } else if self.filled < SIZE {
+            self.filled += 1;
+            self.filled - 1
+        } else {
+            // Pinning always leaves at least one slot evictable.
+            let victim =
+                E::select_victim(&mut self.state, &mut self.slots).expect("No evictable entry");
+            let (reverse_key, _) = unsafe { self.data[victim].assume_init_ref() };
+            self.index_map.remove(reverse_key);
+            unsafe {
+                self.data[victim].assume_init_drop();
+            }
+            E::on_remove(&mut self.state, &mut self.slots, victim);
+            self.stats.evictions = self.stats.evictions.saturating_add(1);
+            victim
+        };
+
+        // At least 1 space must be available
+        self.index_map
+            .insert(key.clone(), index)
+            .expect("No space left");
+        self.data[index].write((key, value));
+        E::on_insert(&mut self.state, &mut self.slots, index);
+
+        self.value(index)
+    }
+
+    /// Removes the entry and returns its value.
+    /// Pinned entries are also removed.
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let index = self.index_map.remove(&key)?;
+        if self.pinned[index] {
+            self.pinned[index] = false;
+            self.pinned_count -= 1;
+        } else {
+            E::on_remove(&mut self.state, &mut self.slots, index);
+        }
+
+        // Every vacant slot was once taken from `filled` or eviction, so it never overflows.
+        self.vacant.push(index).expect("Vacant slots overflowed");
+        let (_, value) = unsafe { self.data[index].assume_init_read() };
+        Some(value)
+    }
+
+    /// Removes all entries including pinned ones.
+    /// Counters are kept.
+    pub fn clear(&mut self) {
+        self.drop_entries();
+        self.index_map.clear();
+        self.filled = 0;
+        self.vacant.clear();
+        self.slots = [E::INITIAL_SLOT; SIZE];
+        self.state = E::INITIAL_STATE;
+        self.pinned = [false; SIZE];
+        self.pinned_count = 0;
+    }
+
+    /// Queries key.
+    /// If found, registered value will return.
+    /// If not found, `generate_value()` will be called and it will register the value.
+    pub fn get_or_else(
+        &mut self,
+        key: K,
+        generate_value: impl FnOnce(K) -> Option<V>,
+    ) -> Option<&V> {
+        match self.index_map.get(&key) {
+            Some(&index) => {
+                self.stats.hits = self.stats.hits.saturating_add(1);
+                self.touch(index);
+                Some(self.value(index))
+            }
+            None => {
+                self.stats.misses = self.stats.misses.saturating_add(1);
+                let Some(new_value) = generate_value(key.clone()) else {
+                    self.stats.generate_failures = self.stats.generate_failures.saturating_add(1);
+                    return None;
+                };
+                Some(self.put(key, new_value))
+            }
+        }
+    }
+
+    /// Pins the entry so that it will never be evicted.
+    /// At least one slot is kept unpinned for new entries.
+    /// Returns `false` if the key is not cached or no more entries can be pinned.
+    pub fn pin(&mut self, key: K) -> bool {
+        let Some(&index) = self.index_map.get(&key) else {
+            return false;
+        };
+        if self.pinned[index] {
+            return true;
+        } else if self.pinned_count + 1 >= SIZE {
+            return false;
+        }
+
+        E::on_remove(&mut self.state, &mut self.slots, index);
+        self.pinned[index] = true;
+        self.pinned_count += 1;
+        true
+    }
+
+    /// Unpins the entry. It becomes the most recently inserted one for eviction strategy.
+    /// Returns whether the key was pinned.
+    pub fn unpin(&mut self, key: K) -> bool {
+        match self.index_map.get(&key) {
+            Some(&index) if self.pinned[index] => {
+                self.unpin_slot(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Unpins all entries.
+    pub fn unpin_all(&mut self) {
+        for index in 0..SIZE {
+            if self.pinned[index] {
+                self.unpin_slot(index);
+            }
+        }
+    }
+
+    /// Whether the key is cached and pinned.
+    pub fn is_pinned(&self, key: K) -> bool {
+        self.index_map
+            .get(&key)
+            .is_some_and(|&index| self.pinned[index])
+    }
+
+    /// Returns the value in occupied slot.
+    fn value(&self, index: usize) -> &V {
+        unsafe { &self.data[index].assume_init_ref().1 }
+    }
+
+    /// Notifies eviction strategy of use of the slot.
+    fn touch(&mut self, index: usize) {
+        if !self.pinned[index] {
+            E::on_hit(&mut self.state, &mut self.slots, index);
+        }
+    }
+
+    /// Returns pinned slot to eviction strategy.
+    fn unpin_slot(&mut self, index: usize) {
+        self.pinned[index] = false;
+        self.pinned_count -= 1;
+        E::on_insert(&mut self.state, &mut self.slots, index);
+    }
+}
+
+impl<K, V, const SIZE: usize, E> SimpleCacheMap<K, V, SIZE, E>
+where
+    E: EvictionStrategy,
+{
+    /// Drops all entries in occupied slots.
+    /// `index_map` must be cleared afterwards.
+    fn drop_entries(&mut self) {
+        for &index in self.index_map.values() {
+            unsafe {
+                self.data[index].assume_init_drop();
+            }
+        }
+    }
+}
+
+impl<K, V, const SIZE: usize, E> Drop for SimpleCacheMap<K, V, SIZE, E>
+where
+    E: EvictionStrategy,
+{
+    fn drop(&mut self) {
+        self.drop_entries();
+    }
+}
@@ -0,0 +1,243 @@
+//! Eviction strategies for `SimpleCacheMap`.
+
+/// Marks the end of slot list.
+const NIL: usize = usize::MAX;
+
+/// Decides which entry `SimpleCacheMap` evicts.
+///
+/// Implementors keep their bookkeeping in `State` (one per cache) and `Slot` (one per entry).
+/// `slots` always has the same length as the cache capacity.
+/// A slot is tracked between `on_insert` and `on_remove`, and only tracked slots can be victims.
+pub trait EvictionStrategy {
+    /// Per-entry bookkeeping.
+    type Slot: Copy;
+
+    /// Whole-cache bookkeeping.
+    type State;
+
+    /// Initial value for each slot.
+    const INITIAL_SLOT: Self::Slot;
+
+    /// Initial value for the state.
+    const INITIAL_STATE: Self::State;
+
+    /// Called when new value is put into the slot.
+    fn on_insert(state: &mut Self::State, slots: &mut [Self::Slot], index: usize);
+
+    /// Called when existing entry is read or overwritten.
+    fn on_hit(state: &mut Self::State, slots: &mut [Self::Slot], index: usize);
+
+    /// Called when the entry is removed from the cache or pinned.
+    fn on_remove(state: &mut Self::State, slots: &mut [Self::Slot], index: usize);
+
+    /// Chooses the slot to evict.
+    /// Called only when all slots are occupied. Returns `None` if no slot is tracked.
+    fn select_victim(state: &mut Self::State, slots: &mut [Self::Slot]) -> Option<usize>;
+}
+
+/// Link of doubly linked slot list.
+#[derive(Debug, Clone, Copy)]
+pub struct ListLink {
+    prev: usize,
+    next: usize,
+}
+
+/// Both ends of doubly linked slot list.
+#[derive(Debug)]
+pub struct ListEnds {
+    head: usize,
+    tail: usize,
+}
+
+impl ListEnds {
+    /// Detaches the slot from list.
+    fn unlink(&mut self, slots: &mut [ListLink], index: usize) {
+        let ListLink { prev, next } = slots[index];
+        match prev {
+            NIL => self.head = next,
+            p => slots[p].next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            n => slots[n].prev = prev,
+        }
+        slots[index] = ListLink {
+            prev: NIL,
+            next: NIL,
+        };
+    }
+
+    /// Inserts detached slot at the front of list.
+    fn push_front(&mut self, slots: &mut [ListLink], index: usize) {
+        slots[index] = ListLink {
+            prev: NIL,
+            next: self.head,
+        };
+        match self.head {
+            NIL => self.tail = index,
+            h => slots[h].prev = index,
+        }
+        self.head = index;
+    }
+}
+
+/// First in, first out.
+/// Evicts the oldest inserted entry regardless of how often it is read.
+pub enum Fifo {}
+
+impl EvictionStrategy for Fifo {
+    type Slot = ListLink;
+    type State = ListEnds;
+
+    const INITIAL_SLOT: ListLink = ListLink {
+        prev: NIL,
+        next: NIL,
+    };
+    const INITIAL_STATE: ListEnds = ListEnds {
+        head: NIL,
+        tail: NIL,
+    };
+
+    fn on_insert(state: &mut ListEnds, slots: &mut [ListLink], index: usize) {
+        state.push_front(slots, index);
+    }
+
+    fn on_hit(_state: &mut ListEnds, _slots: &mut [ListLink], _index: usize) {}
+
+    fn on_remove(state: &mut ListEnds, slots: &mut [ListLink], index: usize) {
+        state.unlink(slots, index);
+    }
+
+    fn select_victim(state: &mut ListEnds, _slots: &mut [ListLink]) -> Option<usize> {
+        (state.tail != NIL).then_some(state.tail)
+    }
+}
+
+/// Least recently used.
+/// Evicts the entry which has not been read for the longest time.
+pub enum Lru {}
+
+impl EvictionStrategy for Lru {
+    type Slot = ListLink;
+    type State = ListEnds;
+
+    const INITIAL_SLOT: ListLink = Fifo::INITIAL_SLOT;
+    const INITIAL_STATE: ListEnds = Fifo::INITIAL_STATE;
+
+    fn on_insert(state: &mut ListEnds, slots: &mut [ListLink], index: usize) {
+        state.push_front(slots, index);
+    }
+
+    fn on_hit(state: &mut ListEnds, slots: &mut [ListLink], index: usize) {
+        if state.head == index {
+            return;
+        }
+        state.unlink(slots, index);
+        state.push_front(slots, index);
+    }
+
+    fn on_remove(state: &mut ListEnds, slots: &mut [ListLink], index: usize) {
+        state.unlink(slots, index);
+    }
+
+    fn select_victim(state: &mut ListEnds, _slots: &mut [ListLink]) -> Option<usize> {
+        (state.tail != NIL).then_some(state.tail)
+    }
+}
+
+/// CLOCK (second chance).
+/// Approximates LRU with a reference bit per entry and a rotating hand.
+pub enum Clock {}
+
+/// Bookkeeping of `Clock` for each slot.
+#[derive(Debug, Clone, Copy)]
+pub struct ClockSlot {
+    tracked: bool,
+    referenced: bool,
+}
+
+impl EvictionStrategy for Clock {
+    type Slot = ClockSlot;
+
+    /// Position of the hand.
+    type State = usize;
+
+    const INITIAL_SLOT: ClockSlot = ClockSlot {
+        tracked: false,
+        referenced: false,
+    };
+    const INITIAL_STATE: usize = 0;
+
+    fn on_insert(_state: &mut usize, slots: &mut [ClockSlot], index: usize) {
+        slots[index] = ClockSlot {
+            tracked: true,
+            referenced: false,
+        };
+    }
+
+    fn on_hit(_state: &mut usize, slots: &mut [ClockSlot], index: usize) {
+        slots[index].referenced = true;
+    }
+
+    fn on_remove(_state: &mut usize, slots: &mut [ClockSlot], index: usize) {
+        slots[index] = Self::INITIAL_SLOT;
+    }
+
+    fn select_victim(hand: &mut usize, slots: &mut [ClockSlot]) -> Option<usize> {
+        // First round clears all reference bits, so second round always finds one if any.
+        for _ in 0..(slots.len() * 2) {
+            let current = *hand;
+            *hand = (current + 1) % slots.len();
+
+            let slot = &mut slots[current];
+            if !slot.tracked {
+                continue;
+            } else if slot.referenced {
+                slot.referenced = false;
+            } else {
+                return Some(current);
+            }
+        }
+        None
+    }
+}
+
+/// Least frequently used.
+/// Evicts the entry with the fewest reads. Suitable for small and stable working sets.
+pub enum Lfu {}
+
+impl EvictionStrategy for Lfu {
+    /// Use count. Zero means untracked.
+    type Slot = u16;
+    type State = ();
+
+    const INITIAL_SLOT: u16 = 0;
+    const INITIAL_STATE: () = ();
+
+    fn on_insert(_state: &mut (), slots: &mut [u16], index: usize) {
+        slots[index] = 1;
+    }
+
+    fn on_hit(_state: &mut (), slots: &mut [u16], index: usize) {
+        // Halves all counts on saturation so that old popularity fades out.
+        if slots[index] == u16::MAX {
+            for count in slots.iter_mut() {
+                *count -= *count >> 1;
+            }
+        }
+        slots[index] += 1;
+    }
+
+    fn on_remove(_state: &mut (), slots: &mut [u16], index: usize) {
+        slots[index] = 0;
+    }
+
+    fn select_victim(_state: &mut (), slots: &mut [u16]) -> Option<usize> {
+        slots
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count != 0)
+            .min_by_key(|(_, &count)| count)
+            .map(|(index, _)| index)
+    }
+}
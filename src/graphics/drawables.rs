@@ -1,35 +1,112 @@
-use crate::graphics::font::{JisFont, JisFontInterface};
+use crate::graphics::font::{FontCell, GlyphSource, JisFontInterface};
 
 use core::{
-    cell::RefCell,
     fmt::{Error as FmtError, Result as FmtResult, Write},
     num::NonZeroUsize,
 };
 
 use embedded_graphics_core::prelude::*;
 
+/// Font interface of the font in `F`.
+type Interface<F> = <<F as FontCell>::Font as GlyphSource>::Interface;
+
+/// JIS geta mark, conventionally put in place of unavailable characters.
+const GETA: char = '\u{3013}';
+
+/// Hex digits in 3x5 pixels, each row from the top in 3 bits from bit 14.
+const HEX_DIGITS: [u16; 16] = [
+    0b111_101_101_101_111,
+    0b010_110_010_010_111,
+    0b111_001_111_100_111,
+    0b111_001_111_001_111,
+    0b101_101_111_001_001,
+    0b111_100_111_001_111,
+    0b111_100_111_101_111,
+    0b111_001_010_010_010,
+    0b111_101_111_101_111,
+    0b111_101_111_001_111,
+    0b010_101_111_101_101,
+    0b110_101_110_101_110,
+    0b011_100_100_100_011,
+    0b110_101_101_101_110,
+    0b111_100_111_100_111,
+    0b111_100_111_100_100,
+];
+
+/// Size of 2x2 hex digits in hex box.
+const HEX_BOX_DIGITS_SIZE: (i32, i32) = (7, 11);
+
+/// What to draw for characters the font has no glyph for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingGlyph {
+    /// Draws nothing, and the following characters close up.
+    #[default]
+    Skip,
+
+    /// Draws geta mark (U+3013), or blank cell if the font lacks it too.
+    Geta,
+
+    /// Draws filled box with lower 4 hex digits of the codepoint cut out.
+    /// Cells smaller than 7x11 get plain filled box.
+    HexBox,
+
+    /// Advances by one blank cell.
+    Blank,
+}
+
+/// What `JisTextStyle::draw_char` has drawn.
+enum DrawnGlyph {
+    /// The font has no glyph, and nothing is drawn.
+    Skipped,
+
+    /// The font has no glyph, and replacement is drawn.
+    Replaced,
+
+    /// Glyph of the character.
+    Single,
+
+    /// Glyph of the character combined with the next one.
+    Combined,
+}
+
+/// What `JisTextStyle::draw_char` draws in a cell, resolved while the font is borrowed.
+enum CellContent<G> {
+    /// Glyph copied out of the font.
+    Glyph(G),
+
+    /// Hex code box of the character.
+    HexBox,
+
+    /// Background color only.
+    Blank,
+
+    /// Nothing, without advancing.
+    Nothing,
+}
+
 /// Pair of font and color.
-pub struct JisTextStyle<'a, 'f, I, C, const CACHE_SIZE: usize>
+/// The font can be held in `RefCell` or, to share it with interrupt handlers, `SharedCell`.
+pub struct JisTextStyle<'a, F, C>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
 {
-    font: &'a RefCell<JisFont<'f, I, CACHE_SIZE>>,
+    font: &'a F,
     fore_color: C,
     back_color: Option<C>,
+    missing_glyph: MissingGlyph,
 }
 
-impl<'a, 'f, I, C, const CACHE_SIZE: usize> JisTextStyle<'a, 'f, I, C, CACHE_SIZE>
+impl<'a, F, C> JisTextStyle<'a, F, C>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
 {
     /// Creates new style.
-    pub fn new(font: &'a RefCell<JisFont<'f, I, CACHE_SIZE>>, fore_color: C) -> Self {
+    pub fn new(font: &'a F, fore_color: C) -> Self {
         JisTextStyle {
             font,
             fore_color,
             back_color: None,
+            missing_glyph: MissingGlyph::Skip,
         }
     }
 
@@ -38,31 +115,149 @@ where
         self.back_color = Some(back_color);
         self
     }
+
+    /// Sets what to draw for characters without glyph.
+    pub fn with_missing_glyph(mut self, missing_glyph: MissingGlyph) -> Self {
+        self.missing_glyph = missing_glyph;
+        self
+    }
+}
+
+impl<'a, F, C> JisTextStyle<'a, F, C>
+where
+    F: FontCell,
+    C: PixelColor,
+{
+    /// Draws a character if the font has its glyph, otherwise its replacement.
+    /// If the character and `next` form a combining sequence in the font, it is drawn instead.
+    /// The font is borrowed only while copying out the glyph, and not while drawing.
+    fn draw_char<D>(
+        &self,
+        target: &mut D,
+        offset: Point,
+        c: char,
+        next: Option<char>,
+    ) -> Result<DrawnGlyph, D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let (content, drawn) = self.font.with_font(|font| {
+            if let Some(glyph) = next.and_then(|n| font.combined_glyph(c, n)) {
+                return (CellContent::Glyph(glyph.clone()), DrawnGlyph::Combined);
+            }
+            if let Some(glyph) = font.glyph(c) {
+                return (CellContent::Glyph(glyph.clone()), DrawnGlyph::Single);
+            }
+            let content = match self.missing_glyph {
+                MissingGlyph::Skip => return (CellContent::Nothing, DrawnGlyph::Skipped),
+                MissingGlyph::Geta => font.glyph(GETA).map_or(CellContent::Blank, |glyph| {
+                    CellContent::Glyph(glyph.clone())
+                }),
+                MissingGlyph::HexBox => CellContent::HexBox,
+                MissingGlyph::Blank => CellContent::Blank,
+            };
+            (content, DrawnGlyph::Replaced)
+        });
+
+        match content {
+            CellContent::Glyph(glyph) => {
+                Interface::<F>::draw(target, offset, self.fore_color, self.back_color, &glyph)?
+            }
+            CellContent::HexBox => self.draw_hex_box(target, offset, c)?,
+            CellContent::Blank => self.draw_blank(target, offset)?,
+            CellContent::Nothing => (),
+        }
+        Ok(drawn)
+    }
+
+    /// Fills a cell with background color, if any.
+    fn draw_blank<D>(&self, target: &mut D, offset: Point) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        match self.back_color {
+            Some(back_color) => {
+                target.draw_iter(cell_points::<F>().map(|p| Pixel(offset + p, back_color)))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Draws a cell filled with fore color, with the codepoint of `c` cut out.
+    fn draw_hex_box<D>(&self, target: &mut D, offset: Point, c: char) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let (width, height) = (Interface::<F>::WIDTH as i32, Interface::<F>::HEIGHT as i32);
+        let (digits_width, digits_height) = HEX_BOX_DIGITS_SIZE;
+        let digits_offset = Point::new((width - digits_width) / 2, (height - digits_height) / 2);
+        let fits = digits_offset.x >= 0 && digits_offset.y >= 0;
+
+        let pixels = cell_points::<F>().filter_map(|p| {
+            if fits && is_hex_digit_pixel(c as u32, p - digits_offset) {
+                self.back_color
+                    .map(|back_color| Pixel(offset + p, back_color))
+            } else {
+                Some(Pixel(offset + p, self.fore_color))
+            }
+        });
+        target.draw_iter(pixels)
+    }
+}
+
+/// Enumerates points in a cell.
+fn cell_points<F: FontCell>() -> impl Iterator<Item = Point> {
+    let (width, height) = (Interface::<F>::WIDTH as i32, Interface::<F>::HEIGHT as i32);
+    (0..height).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+}
+
+/// Whether the point in 2x2 hex digits of lower 16 bits in `code` is set.
+fn is_hex_digit_pixel(code: u32, point: Point) -> bool {
+    let (digits_width, digits_height) = HEX_BOX_DIGITS_SIZE;
+    if !(0..digits_width).contains(&point.x) || !(0..digits_height).contains(&point.y) {
+        return false;
+    }
+
+    // Digits are 3x5 pixels with 1 pixel spacing.
+    let (column, x) = (point.x / 4, point.x % 4);
+    let (row, y) = (point.y / 6, point.y % 6);
+    if x == 3 || y == 5 {
+        return false;
+    }
+    let digit = (code >> (12 - 4 * (row * 2 + column))) & 0xF;
+    HEX_DIGITS[digit as usize] & (1 << (14 - (y * 3 + x))) != 0
+}
+
+/// Result of drawing `JisText`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JisTextOutput {
+    /// Width of drawn area in pixels.
+    pub width: usize,
+
+    /// Height of drawn area in pixels.
+    pub height: usize,
+
+    /// Number of characters the font has no glyph for.
+    pub missing: usize,
 }
 
 /// Text to draw with `JisTextStyle`.
-pub struct JisText<'a, 'f, I, C, const CACHE_SIZE: usize>
+pub struct JisText<'a, F, C>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
 {
     text: &'a str,
-    style: &'a JisTextStyle<'a, 'f, I, C, CACHE_SIZE>,
+    style: &'a JisTextStyle<'a, F, C>,
     offset: Point,
     wrapping_width: Option<NonZeroUsize>,
 }
 
-impl<'a, 'f, I, C, const CACHE_SIZE: usize> JisText<'a, 'f, I, C, CACHE_SIZE>
+impl<'a, F, C> JisText<'a, F, C>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
 {
     /// Constructs new text to draw.
-    pub fn new(
-        text: &'a str,
-        offset: Point,
-        style: &'a JisTextStyle<'a, 'f, I, C, CACHE_SIZE>,
-    ) -> Self {
+    pub fn new(text: &'a str, offset: Point, style: &'a JisTextStyle<'a, F, C>) -> Self {
         JisText {
             text,
             offset,
@@ -78,38 +273,41 @@ where
     }
 }
 
-impl<'a, 'f, I, C, const CACHE_SIZE: usize> Drawable for JisText<'a, 'f, I, C, CACHE_SIZE>
+impl<'a, F, C> Drawable for JisText<'a, F, C>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
     C: PixelColor,
 {
     type Color = C;
-    type Output = (usize, usize);
+    type Output = JisTextOutput;
 
-    fn draw<D>(&self, target: &mut D) -> Result<(usize, usize), D::Error>
+    fn draw<D>(&self, target: &mut D) -> Result<JisTextOutput, D::Error>
     where
         D: DrawTarget<Color = C>,
     {
-        let mut font_cache = self.style.font.borrow_mut();
         let mut max_relx = 0;
         let (mut relx, mut rely) = (0, 0);
         let mut chars_in_line = 0;
+        let mut missing = 0;
         for line in self.text.lines() {
-            for draw_char in line.chars() {
-                let glyph_source = match font_cache.query(draw_char) {
-                    Some(g) => g,
-                    None => continue,
-                };
+            let mut chars = line.chars().peekable();
+            while let Some(draw_char) = chars.next() {
                 let char_offset = Point::new(self.offset.x + relx, self.offset.y + rely);
-                I::draw(
-                    target,
-                    char_offset,
-                    self.style.fore_color,
-                    self.style.back_color,
-                    glyph_source,
-                )?;
-                relx += I::WIDTH as i32;
+                match self
+                    .style
+                    .draw_char(target, char_offset, draw_char, chars.peek().copied())?
+                {
+                    DrawnGlyph::Skipped => {
+                        missing += 1;
+                        continue;
+                    }
+                    DrawnGlyph::Replaced => missing += 1,
+                    DrawnGlyph::Single => (),
+                    DrawnGlyph::Combined => {
+                        chars.next();
+                    }
+                }
+                relx += Interface::<F>::WIDTH as i32;
 
                 // Line wrapping.
                 if let Some(wrap) = self.wrapping_width {
@@ -117,48 +315,47 @@ where
                     if chars_in_line >= wrap.get() {
                         max_relx = max_relx.max(relx);
                         relx = 0;
-                        rely += I::HEIGHT as i32;
+                        rely += Interface::<F>::HEIGHT as i32;
                         chars_in_line = 0;
                     }
                 }
             }
             max_relx = max_relx.max(relx);
             relx = 0;
-            rely += I::HEIGHT as i32;
+            rely += Interface::<F>::HEIGHT as i32;
             chars_in_line = 0;
         }
 
-        Ok((max_relx as usize, rely as usize))
+        Ok(JisTextOutput {
+            width: max_relx as usize,
+            height: rely as usize,
+            missing,
+        })
     }
 }
 
 /// Text to draw with `JisTextStyle`.
-pub struct JisTextDirect<'a, 'f, I, C, D, const CACHE_SIZE: usize>
+pub struct JisTextDirect<'a, F, C, D>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
 {
     draw_target: &'a mut D,
-    style: &'a JisTextStyle<'a, 'f, I, C, CACHE_SIZE>,
+    style: &'a JisTextStyle<'a, F, C>,
     offset: Point,
     wrapping_width: Option<NonZeroUsize>,
     chars_in_line: usize,
     relx: i32,
     rely: i32,
+    missing: usize,
 }
 
-impl<'a, 'f, I, C, D, const CACHE_SIZE: usize> JisTextDirect<'a, 'f, I, C, D, CACHE_SIZE>
+impl<'a, F, C, D> JisTextDirect<'a, F, C, D>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
     D: DrawTarget<Color = C>,
 {
     /// Constructs new text to draw.
-    pub fn new(
-        draw_target: &'a mut D,
-        offset: Point,
-        style: &'a JisTextStyle<'a, 'f, I, C, CACHE_SIZE>,
-    ) -> Self {
+    pub fn new(draw_target: &'a mut D, offset: Point, style: &'a JisTextStyle<'a, F, C>) -> Self {
         JisTextDirect {
             draw_target,
             offset,
@@ -167,6 +364,7 @@ where
             chars_in_line: 0,
             relx: 0,
             rely: 0,
+            missing: 0,
         }
     }
 
@@ -175,46 +373,59 @@ where
         self.wrapping_width = NonZeroUsize::new(width);
         self
     }
+
+    /// Returns the number of characters written so far the font has no glyph for.
+    pub fn missing_glyphs(&self) -> usize {
+        self.missing
+    }
 }
 
-impl<'a, 'f, I, C, D, const CACHE_SIZE: usize> Write for JisTextDirect<'a, 'f, I, C, D, CACHE_SIZE>
+impl<'a, F, C, D> Write for JisTextDirect<'a, F, C, D>
 where
-    'f: 'a,
-    I: JisFontInterface,
+    F: FontCell,
     C: PixelColor,
     D: DrawTarget<Color = C>,
 {
     fn write_str(&mut self, s: &str) -> FmtResult {
-        let mut font_cache = self.style.font.borrow_mut();
         for line in s.lines() {
-            for draw_char in line.chars() {
-                let glyph_source = match font_cache.query(draw_char) {
-                    Some(g) => g,
-                    None => continue,
-                };
+            // Combining sequences split across calls are drawn separately.
+            let mut chars = line.chars().peekable();
+            while let Some(draw_char) = chars.next() {
                 let char_offset = Point::new(self.offset.x + self.relx, self.offset.y + self.rely);
-                I::draw(
-                    self.draw_target,
-                    char_offset,
-                    self.style.fore_color,
-                    self.style.back_color,
-                    glyph_source,
-                )
-                .map_err(|_| FmtError)?;
-                self.relx += I::WIDTH as i32;
+                let drawn = self
+                    .style
+                    .draw_char(
+                        self.draw_target,
+                        char_offset,
+                        draw_char,
+                        chars.peek().copied(),
+                    )
+                    .map_err(|_| FmtError)?;
+                match drawn {
+                    DrawnGlyph::Skipped => {
+                        self.missing += 1;
+                        continue;
+                    }
+                    DrawnGlyph::Replaced => self.missing += 1,
+                    DrawnGlyph::Single => (),
+                    DrawnGlyph::Combined => {
+                        chars.next();
+                    }
+                }
+                self.relx += Interface::<F>::WIDTH as i32;
 
                 // Line wrapping.
                 if let Some(wrap) = self.wrapping_width {
                     self.chars_in_line += 1;
                     if self.chars_in_line >= wrap.get() {
                         self.relx = 0;
-                        self.rely += I::HEIGHT as i32;
+                        self.rely += Interface::<F>::HEIGHT as i32;
                         self.chars_in_line = 0;
                     }
                 }
             }
             self.relx = 0;
-            self.rely += I::HEIGHT as i32;
+            self.rely += Interface::<F>::HEIGHT as i32;
             self.chars_in_line = 0;
         }
         Ok(())
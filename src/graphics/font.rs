@@ -1,18 +1,24 @@
 use crate::{
-    cache::SimpleCacheMap,
-    string::{Uni2JisTableError, Unicode2JisTable, JIS_KUTEN_WIDTH},
+    cache::{
+        strategy::{EvictionStrategy, Lru},
+        CacheStats, SimpleCacheMap,
+    },
+    graphics::mapper::{GlyphMapper, KutenMapper},
+    string::{Charset94, Jis0208, Jis0213, Uni2JisTableError, Unicode2KutenTable},
+    sync::SharedCell,
 };
 
 use core::cell::RefCell;
 
 use embedded_graphics_core::prelude::*;
 
-/// Conversion table binary of Unicode codepoint to JIS kuten code.
-const UNI2JIS_DATA: &[u8] = include_bytes!("../../assets/uni2jis.bin");
-
+/// Glyph bitmap layout indexed by glyph index.
+/// Kuten bitmaps, where index is `(ku - 1) * 94 + (ten - 1)`, may hold one plane,
+/// or two planes for JIS X 0213 where ku continues to 188.
 pub trait JisFontInterface {
     /// Cached type of glyph.
-    type Cached: Default + Copy;
+    /// Drawables clone it out of the font, so that drawing does not hold the font.
+    type Cached: Clone;
 
     /// Glyph width.
     const WIDTH: usize;
@@ -20,12 +26,13 @@ pub trait JisFontInterface {
     /// Glyph height.
     const HEIGHT: usize;
 
-    /// Validates input bitmap.
-    /// Returns whether the bitmap is valid for this interface.
-    fn validate_bitmap(bitmap: &[u8]) -> bool;
+    /// Returns the number of glyphs in the bitmap.
+    /// Returns `None` if the bitmap is malformed for this interface.
+    fn glyphs_count(bitmap: &[u8]) -> Option<usize>;
 
     /// Fetches glyph from bitmap into cached form.
-    fn fetch(bitmap: &[u8], kuten: (u8, u8)) -> Self::Cached;
+    /// Returns `None` if the bitmap does not cover the index.
+    fn fetch(bitmap: &[u8], index: usize) -> Option<Self::Cached>;
 
     /// Draw a character.
     fn draw<C: PixelColor, D: DrawTarget<Color = C>>(
@@ -37,53 +44,346 @@ pub trait JisFontInterface {
     ) -> Result<(), D::Error>;
 }
 
+/// Font which resolves characters into cached glyphs.
+pub trait GlyphSource {
+    /// Interface to draw glyphs with.
+    type Interface: JisFontInterface;
+
+    /// Resolves a character into glyph.
+    fn glyph(&mut self, c: char) -> Option<&<Self::Interface as JisFontInterface>::Cached>;
+
+    /// Resolves a pair of base and combining character into single glyph.
+    /// Returns `None` if the font has no glyph for the sequence.
+    fn combined_glyph(
+        &mut self,
+        _base: char,
+        _combining: char,
+    ) -> Option<&<Self::Interface as JisFontInterface>::Cached> {
+        None
+    }
+}
+
+/// Cell which lends its font mutably through shared reference.
+/// Drawables hold the font through this.
+pub trait FontCell {
+    /// Font in the cell.
+    type Font: GlyphSource;
+
+    /// Runs `f` with exclusive access to the font.
+    fn with_font<R>(&self, f: impl FnOnce(&mut Self::Font) -> R) -> R;
+}
+
+impl<G: GlyphSource> FontCell for RefCell<G> {
+    type Font = G;
+
+    fn with_font<R>(&self, f: impl FnOnce(&mut G) -> R) -> R {
+        f(&mut self.borrow_mut())
+    }
+}
+
+impl<G: GlyphSource> FontCell for SharedCell<G> {
+    type Font = G;
+
+    fn with_font<R>(&self, f: impl FnOnce(&mut G) -> R) -> R {
+        self.with(f)
+    }
+}
+
 /// Represents a drawable font data based on JIS encoding.
-pub struct JisFont<'a, I, const CACHE_SIZE: usize>
+pub type JisFont<'a, I, const CACHE_SIZE: usize, E = Lru> =
+    KutenFont<'a, Jis0208, I, CACHE_SIZE, E>;
+
+/// Represents a drawable font data based on JIS X 0213.
+/// The bitmap can be plane 1 only; plane 2 characters are not resolved then.
+pub type Jis0213Font<'a, I, const CACHE_SIZE: usize, E = Lru> =
+    KutenFont<'a, Jis0213, I, CACHE_SIZE, E>;
+
+/// Glyph cache key; a character with combining character if any.
+type GlyphKey = (char, Option<char>);
+
+/// Represents a drawable font data based on 94x94 charset `S`.
+/// The built-in conversion table of `S` is linked only if `new` or `new_uninit` is used.
+pub type KutenFont<'a, S, I, const CACHE_SIZE: usize, E = Lru> =
+    MappedFont<'a, KutenMapper<'a, S>, I, CACHE_SIZE, E>;
+
+/// Represents a drawable font data whose glyphs are indexed by mapper `M`.
+/// Glyph cache entries are evicted by strategy `E`.
+pub struct MappedFont<'a, M, I, const CACHE_SIZE: usize, E = Lru>
 where
+    M: GlyphMapper,
     I: JisFontInterface,
+    E: EvictionStrategy,
 {
-    uni2jis_table: Unicode2JisTable<'a>,
-    font_cache: SimpleCacheMap<u16, I::Cached, CACHE_SIZE>,
+    mapper: M,
+    font_cache: SimpleCacheMap<GlyphKey, I::Cached, CACHE_SIZE, E>,
     font_bitmap: &'a [u8],
+    initialized: bool,
 }
 
-impl<'a, I, const CACHE_SIZE: usize> JisFont<'a, I, CACHE_SIZE>
+impl<'a, S, I, const CACHE_SIZE: usize, E> KutenFont<'a, S, I, CACHE_SIZE, E>
 where
+    S: Charset94,
     I: JisFontInterface,
+    E: EvictionStrategy,
 {
-    /// Creates new font with font bitmap data.
+    /// Creates new font with font bitmap data and built-in conversion table of `S`.
     pub fn new(font_bitmap: &'a [u8]) -> Result<RefCell<Self>, JisFontError> {
-        if !I::validate_bitmap(font_bitmap) {
-            return Err(JisFontError::InvalidFontBitmap);
-        }
+        MappedFont::with_mapper(font_bitmap, KutenMapper::builtin())
+    }
 
-        let uni2jis_table =
-            Unicode2JisTable::new(UNI2JIS_DATA).map_err(|e| JisFontError::InvalidUni2Jis(e))?;
+    /// Creates new font with font bitmap data and conversion table.
+    pub fn with_table(
+        font_bitmap: &'a [u8],
+        uni2kuten_table: Unicode2KutenTable<'a, S>,
+    ) -> Result<RefCell<Self>, JisFontError> {
+        MappedFont::with_mapper(font_bitmap, KutenMapper::from_table(uni2kuten_table))
+    }
 
-        Ok(RefCell::new(JisFont {
-            uni2jis_table,
+    /// Creates new font with font bitmap data and conversion table binary.
+    /// The binary can be any version `Unicode2KutenTable::new` accepts.
+    pub fn with_table_bytes(
+        font_bitmap: &'a [u8],
+        uni2kuten_bytes: &'a [u8],
+    ) -> Result<RefCell<Self>, JisFontError> {
+        MappedFont::with_mapper(font_bitmap, KutenMapper::from_bytes(uni2kuten_bytes))
+    }
+
+    /// Creates new font without validation, so that it can be placed in `static`.
+    /// No glyphs will be resolved until `init()` succeeds.
+    pub const fn new_uninit(font_bitmap: &'a [u8]) -> Self {
+        MappedFont::new_uninit_with_mapper(font_bitmap, KutenMapper::builtin())
+    }
+
+    /// Creates new font with conversion table, without validation.
+    /// No glyphs will be resolved until `init()` succeeds.
+    pub const fn new_uninit_with_table(
+        font_bitmap: &'a [u8],
+        uni2kuten_table: Unicode2KutenTable<'a, S>,
+    ) -> Self {
+        MappedFont::new_uninit_with_mapper(font_bitmap, KutenMapper::from_table(uni2kuten_table))
+    }
+
+    /// Creates new font with conversion table binary, without validation.
+    /// The binary is parsed in `init()`.
+    pub const fn new_uninit_with_table_bytes(
+        font_bitmap: &'a [u8],
+        uni2kuten_bytes: &'a [u8],
+    ) -> Self {
+        MappedFont::new_uninit_with_mapper(font_bitmap, KutenMapper::from_bytes(uni2kuten_bytes))
+    }
+
+    /// Replaces conversion table.
+    /// All cached glyphs including pinned ones are discarded.
+    pub fn set_table(&mut self, uni2kuten_table: Unicode2KutenTable<'a, S>) {
+        self.mapper.set_table(uni2kuten_table);
+        self.font_cache.clear();
+    }
+
+    /// Enables or disables substitutes for characters missing from the table.
+    /// Enabled by default. All cached glyphs including pinned ones are discarded.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.mapper.set_fallback(enabled);
+        self.font_cache.clear();
+    }
+}
+
+impl<'a, M, I, const CACHE_SIZE: usize, E> MappedFont<'a, M, I, CACHE_SIZE, E>
+where
+    M: GlyphMapper,
+    I: JisFontInterface,
+    E: EvictionStrategy,
+{
+    /// Creates new font with font bitmap data and mapper.
+    pub fn with_mapper(font_bitmap: &'a [u8], mapper: M) -> Result<RefCell<Self>, JisFontError> {
+        let mut font = MappedFont::new_uninit_with_mapper(font_bitmap, mapper);
+        font.init()?;
+        Ok(RefCell::new(font))
+    }
+
+    /// Creates new font with mapper, without validation.
+    /// No glyphs will be resolved until `init()` succeeds.
+    pub const fn new_uninit_with_mapper(font_bitmap: &'a [u8], mapper: M) -> Self {
+        MappedFont {
+            mapper,
             font_bitmap,
             font_cache: SimpleCacheMap::new(),
-        }))
+            initialized: false,
+        }
+    }
+
+    /// Prepares mapper and validates font bitmap data against it.
+    /// Calling it again after success does nothing.
+    pub fn init(&mut self) -> Result<(), JisFontError> {
+        if self.initialized {
+            return Ok(());
+        }
+
+        self.mapper.init()?;
+        if !self.validate_bitmap(self.font_bitmap) {
+            return Err(JisFontError::InvalidFontBitmap);
+        }
+        self.initialized = true;
+        Ok(())
+    }
+
+    /// Replaces font bitmap data.
+    /// All cached glyphs including pinned ones are discarded.
+    pub fn set_bitmap(&mut self, font_bitmap: &'a [u8]) -> Result<(), JisFontError> {
+        if !self.validate_bitmap(font_bitmap) {
+            return Err(JisFontError::InvalidFontBitmap);
+        }
+
+        self.font_bitmap = font_bitmap;
+        self.font_cache.clear();
+        Ok(())
+    }
+
+    /// Returns mapper.
+    pub fn mapper(&self) -> &M {
+        &self.mapper
+    }
+
+    /// Discards all cached glyphs including pinned ones.
+    pub fn clear_cache(&mut self) {
+        self.font_cache.clear();
+    }
+
+    /// Returns glyph cache counters.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.font_cache.stats()
+    }
+
+    /// Resets glyph cache counters.
+    pub fn reset_cache_stats(&mut self) {
+        self.font_cache.reset_stats();
+    }
+
+    /// Loads all glyphs in the text into the cache ahead of drawing.
+    /// Glyphs loaded earlier may be evicted if the text has more characters than the cache size.
+    pub fn preload(&mut self, text: &str) -> PreloadReport {
+        let mut report = PreloadReport::default();
+        for preload_char in text.chars() {
+            if self.font_cache.contains_key((preload_char, None)) {
+                report.cached += 1;
+            } else if self.query(preload_char).is_some() {
+                report.loaded += 1;
+            } else {
+                report.unmappable += 1;
+            }
+        }
+        report
+    }
+
+    /// Iterates over characters in the text which have no glyph.
+    /// The cache is not modified.
+    pub fn unmappable_chars<'t>(&'t self, text: &'t str) -> impl Iterator<Item = char> + 't {
+        text.chars().filter(move |&c| !self.has_glyph(c))
+    }
+
+    /// Loads all glyphs in the text and pins them in the cache.
+    /// Returns the number of characters which could not be loaded or pinned.
+    pub fn pin_str(&mut self, text: &str) -> usize {
+        let mut failed = 0;
+        for pin_char in text.chars() {
+            if self.query(pin_char).is_none() || !self.font_cache.pin((pin_char, None)) {
+                failed += 1;
+            }
+        }
+        failed
+    }
+
+    /// Unpins all glyphs in the text.
+    pub fn unpin_str(&mut self, text: &str) {
+        for unpin_char in text.chars() {
+            self.font_cache.unpin((unpin_char, None));
+        }
+    }
+
+    /// Unpins all glyphs.
+    pub fn unpin_all(&mut self) {
+        self.font_cache.unpin_all();
+    }
+
+    /// Whether the character has a glyph, checked in the same way as `query` without the cache.
+    fn has_glyph(&self, c: char) -> bool {
+        self.initialized
+            && self
+                .mapper
+                .map(c)
+                .and_then(|index| I::fetch(self.font_bitmap, index))
+                .is_some()
+    }
+
+    /// Whether the bitmap has enough glyphs for the mapper.
+    fn validate_bitmap(&self, font_bitmap: &[u8]) -> bool {
+        I::glyphs_count(font_bitmap).is_some_and(|count| count >= self.mapper.glyphs_count())
     }
 
     /// Queries font cache.
     pub(crate) fn query(&mut self, draw_char: char) -> Option<&I::Cached> {
-        self.font_cache.get_or_else(draw_char as u16, |_| {
-            let kuten = self.uni2jis_table.query(draw_char)?;
-            Some(I::fetch(self.font_bitmap, kuten))
+        if !self.initialized {
+            return None;
+        }
+        self.font_cache.get_or_else((draw_char, None), |_| {
+            I::fetch(self.font_bitmap, self.mapper.map(draw_char)?)
         })
     }
+
+    /// Queries font cache for combining sequence.
+    pub(crate) fn query_sequence(&mut self, base: char, combining: char) -> Option<&I::Cached> {
+        if !self.initialized {
+            return None;
+        }
+
+        // Most pairs are not sequences; check before polluting the cache.
+        let index = self.mapper.map_sequence(base, combining)?;
+        self.font_cache.get_or_else((base, Some(combining)), |_| {
+            I::fetch(self.font_bitmap, index)
+        })
+    }
+}
+
+impl<'a, M, I, const CACHE_SIZE: usize, E> GlyphSource for MappedFont<'a, M, I, CACHE_SIZE, E>
+where
+    M: GlyphMapper,
+    I: JisFontInterface,
+    E: EvictionStrategy,
+{
+    type Interface = I;
+
+    fn glyph(&mut self, c: char) -> Option<&I::Cached> {
+        self.query(c)
+    }
+
+    fn combined_glyph(&mut self, base: char, combining: char) -> Option<&I::Cached> {
+        self.query_sequence(base, combining)
+    }
+}
+
+/// Result of `MappedFont::preload`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PreloadReport {
+    /// Characters whose glyph was newly loaded.
+    pub loaded: usize,
+
+    /// Characters whose glyph was already cached.
+    pub cached: usize,
+
+    /// Characters which have no glyph.
+    /// Use `MappedFont::unmappable_chars` to list them.
+    pub unmappable: usize,
 }
 
-/// `JisFont` errors.
+/// `MappedFont` errors.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum JisFontError {
     InvalidUni2Jis(Uni2JisTableError),
     InvalidFontBitmap,
+    MissingTable,
+    InvalidMapping,
 }
 
-/// 8x12 JIS font interface.
+/// 8x12 font interface, such as for 94x94 kuten bitmap of JIS X 0208, GB 2312 or KS X 1001.
 pub enum JisFont8x12 {}
 
 impl JisFontInterface for JisFont8x12 {
@@ -91,16 +391,16 @@ impl JisFontInterface for JisFont8x12 {
     const WIDTH: usize = 8;
     const HEIGHT: usize = 12;
 
-    fn validate_bitmap(bitmap: &[u8]) -> bool {
-        let expected = JIS_KUTEN_WIDTH * JIS_KUTEN_WIDTH * 12;
-        bitmap.len() == expected
+    // `usize::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn glyphs_count(bitmap: &[u8]) -> Option<usize> {
+        (bitmap.len() % 12 == 0).then_some(bitmap.len() / 12)
     }
 
-    fn fetch(bitmap: &[u8], (ku, ten): (u8, u8)) -> Self::Cached {
+    fn fetch(bitmap: &[u8], index: usize) -> Option<Self::Cached> {
         let mut b = [0; 12];
-        let base_index = (ku as usize - 1) * JIS_KUTEN_WIDTH + (ten as usize - 1);
-        b.copy_from_slice(&bitmap[(base_index * 12)..((base_index + 1) * 12)]);
-        b
+        b.copy_from_slice(bitmap.get((index * 12)..((index + 1) * 12))?);
+        Some(b)
     }
 
     fn draw<C: PixelColor, D: DrawTarget<Color = C>>(
@@ -0,0 +1,242 @@
+use crate::{
+    graphics::font::JisFontError,
+    string::{
+        kana::compose_sound_mark, transliterate::find_with_fallback, Charset94, Unicode2KutenTable,
+        KUTEN_WIDTH,
+    },
+};
+
+/// Maps characters into glyph index of bitmap font.
+pub trait GlyphMapper {
+    /// Minimum number of glyphs the bitmap must have.
+    fn glyphs_count(&self) -> usize;
+
+    /// Prepares and validates the mapping.
+    /// Fonts call this once in their `init()`.
+    fn init(&mut self) -> Result<(), JisFontError> {
+        Ok(())
+    }
+
+    /// Maps a character into glyph index.
+    fn map(&self, c: char) -> Option<usize>;
+
+    /// Maps a pair of base and combining character into single glyph index.
+    fn map_sequence(&self, _base: char, _combining: char) -> Option<usize> {
+        None
+    }
+}
+
+impl<M: GlyphMapper + ?Sized> GlyphMapper for &mut M {
+    fn glyphs_count(&self) -> usize {
+        (**self).glyphs_count()
+    }
+
+    fn init(&mut self) -> Result<(), JisFontError> {
+        (**self).init()
+    }
+
+    fn map(&self, c: char) -> Option<usize> {
+        (**self).map(c)
+    }
+
+    fn map_sequence(&self, base: char, combining: char) -> Option<usize> {
+        (**self).map_sequence(base, combining)
+    }
+}
+
+/// Converts kuten code into glyph index of kuten bitmap.
+fn kuten_index((ku, ten): (u8, u8)) -> usize {
+    (ku as usize - 1) * KUTEN_WIDTH + (ten as usize - 1)
+}
+
+/// Kuten bitmap has at least one 94x94 plane.
+impl<'a, S: Charset94> GlyphMapper for Unicode2KutenTable<'a, S> {
+    fn glyphs_count(&self) -> usize {
+        KUTEN_WIDTH * KUTEN_WIDTH
+    }
+
+    fn map(&self, c: char) -> Option<usize> {
+        self.query(c).map(kuten_index)
+    }
+
+    fn map_sequence(&self, base: char, combining: char) -> Option<usize> {
+        self.query_sequence(base, combining).map(kuten_index)
+    }
+}
+
+/// Kuten mapper whose conversion table binary is parsed in `init()`.
+/// Characters missing from the table are retried with substitutes by `fallback_char`,
+/// and kana followed by sound mark is composed, unless fallback is disabled.
+#[derive(Clone, Copy)]
+pub struct KutenMapper<'a, S> {
+    table: Option<Unicode2KutenTable<'a, S>>,
+    table_bytes: Option<&'a [u8]>,
+    fallback: bool,
+}
+
+impl<'a, S: Charset94> KutenMapper<'a, S> {
+    /// Uses built-in conversion table of `S`.
+    /// The table is linked only if this is used.
+    pub const fn builtin() -> KutenMapper<'a, S> {
+        KutenMapper {
+            table: None,
+            table_bytes: S::UNI2KUTEN_DATA,
+            fallback: true,
+        }
+    }
+
+    /// Uses conversion table binary.
+    pub const fn from_bytes(table_bytes: &'a [u8]) -> KutenMapper<'a, S> {
+        KutenMapper {
+            table: None,
+            table_bytes: Some(table_bytes),
+            fallback: true,
+        }
+    }
+
+    /// Uses parsed conversion table.
+    pub const fn from_table(table: Unicode2KutenTable<'a, S>) -> KutenMapper<'a, S> {
+        KutenMapper {
+            table: Some(table),
+            table_bytes: None,
+            fallback: true,
+        }
+    }
+
+    /// Replaces conversion table, keeping fallback setting.
+    pub fn set_table(&mut self, table: Unicode2KutenTable<'a, S>) {
+        self.table = Some(table);
+        self.table_bytes = None;
+    }
+
+    /// Enables or disables fallback.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Returns conversion table if parsed.
+    pub fn table(&self) -> Option<&Unicode2KutenTable<'a, S>> {
+        self.table.as_ref()
+    }
+}
+
+impl<'a, S: Charset94> GlyphMapper for KutenMapper<'a, S> {
+    fn glyphs_count(&self) -> usize {
+        KUTEN_WIDTH * KUTEN_WIDTH
+    }
+
+    fn init(&mut self) -> Result<(), JisFontError> {
+        if self.table.is_none() {
+            let table_bytes = self.table_bytes.ok_or(JisFontError::MissingTable)?;
+            let table =
+                Unicode2KutenTable::new(table_bytes).map_err(JisFontError::InvalidUni2Jis)?;
+            self.table = Some(table);
+        }
+        Ok(())
+    }
+
+    fn map(&self, c: char) -> Option<usize> {
+        let table = self.table.as_ref()?;
+        find_with_fallback(c, self.fallback, |s| table.map(s))
+    }
+
+    fn map_sequence(&self, base: char, combining: char) -> Option<usize> {
+        let table = self.table.as_ref()?;
+        table.map_sequence(base, combining).or_else(|| {
+            let composed = compose_sound_mark(base, combining).filter(|_| self.fallback)?;
+            self.map(composed)
+        })
+    }
+}
+
+/// Range of characters laid out consecutively in bitmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlyphRange {
+    first: char,
+    last: char,
+    first_glyph: usize,
+}
+
+impl GlyphRange {
+    /// Maps `first..=last` into glyphs from `first_glyph`.
+    pub const fn new(first: char, last: char, first_glyph: usize) -> GlyphRange {
+        GlyphRange {
+            first,
+            last,
+            first_glyph,
+        }
+    }
+
+    /// Returns the index of glyph after this range.
+    /// Inverted range is counted as empty, so that it is reported by `init()` rather than panics.
+    const fn end_glyph(&self) -> usize {
+        let length = (self.last as usize + 1).saturating_sub(self.first as usize);
+        self.first_glyph.saturating_add(length)
+    }
+}
+
+/// Maps characters by Unicode ranges, such as Latin-1 (`' '..='\u{FF}'`),
+/// Cyrillic (`'\u{400}'..='\u{4FF}'`) or box drawing (`'\u{2500}'..='\u{257F}'`).
+/// Earlier ranges take precedence.
+#[derive(Debug, Clone, Copy)]
+pub struct UnicodeRanges<'a> {
+    ranges: &'a [GlyphRange],
+}
+
+impl<'a> UnicodeRanges<'a> {
+    /// Constructs from ranges.
+    pub const fn new(ranges: &'a [GlyphRange]) -> UnicodeRanges<'a> {
+        UnicodeRanges { ranges }
+    }
+}
+
+impl<'a> GlyphMapper for UnicodeRanges<'a> {
+    fn glyphs_count(&self) -> usize {
+        self.ranges.iter().map(|r| r.end_glyph()).max().unwrap_or(0)
+    }
+
+    fn init(&mut self) -> Result<(), JisFontError> {
+        if self.ranges.iter().any(|r| r.first > r.last) {
+            return Err(JisFontError::InvalidMapping);
+        }
+        Ok(())
+    }
+
+    fn map(&self, c: char) -> Option<usize> {
+        let range = self
+            .ranges
+            .iter()
+            .find(|r| (r.first..=r.last).contains(&c))?;
+        Some(range.first_glyph + (c as usize - range.first as usize))
+    }
+}
+
+/// Maps characters by sorted list, where glyph `i` is for `chars[i]`.
+#[derive(Debug, Clone, Copy)]
+pub struct SparseGlyphs<'a> {
+    chars: &'a [char],
+}
+
+impl<'a> SparseGlyphs<'a> {
+    /// Constructs from sorted character list.
+    pub const fn new(chars: &'a [char]) -> SparseGlyphs<'a> {
+        SparseGlyphs { chars }
+    }
+}
+
+impl<'a> GlyphMapper for SparseGlyphs<'a> {
+    fn glyphs_count(&self) -> usize {
+        self.chars.len()
+    }
+
+    fn init(&mut self) -> Result<(), JisFontError> {
+        if self.chars.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(JisFontError::InvalidMapping);
+        }
+        Ok(())
+    }
+
+    fn map(&self, c: char) -> Option<usize> {
+        self.chars.binary_search(&c).ok()
+    }
+}
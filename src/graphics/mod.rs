@@ -1,2 +1,3 @@
 pub mod drawables;
 pub mod font;
+pub mod mapper;
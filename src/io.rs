@@ -1,13 +1,21 @@
 //! Formatting functionalities for no_std environment.
 
-use crate::string::extract_valid_str;
+use crate::string::{
+    utf8::{Utf8DecodeError, Utf8Decoder},
+    REPLACEMENT_CHAR,
+};
+
+use core::str::from_utf8;
 
 /// Constructs string lines from small packets.
+/// Lines are kept as received, and also decoded as UTF-8 with U+FFFD for invalid sequences.
 pub struct LineReader<const BUFFER_SIZE: usize> {
     ongoing_buffer: [u8; BUFFER_SIZE],
     ongoing_written: usize,
     ready_buffer: [u8; BUFFER_SIZE],
     ready_size: usize,
+    ready_text: [u8; BUFFER_SIZE],
+    ready_decoded: DecodedLine,
 }
 
 impl<const BUFFER_SIZE: usize> LineReader<BUFFER_SIZE> {
@@ -18,32 +26,43 @@ impl<const BUFFER_SIZE: usize> LineReader<BUFFER_SIZE> {
             ongoing_written: 0,
             ready_buffer: [0; BUFFER_SIZE],
             ready_size: 0,
+            ready_text: [0; BUFFER_SIZE],
+            ready_decoded: DecodedLine::EMPTY,
         }
     }
 
-    /// If any line is ready, returns it.
+    /// If any line is ready, returns it as received.
     pub fn ready_bytes(&self) -> Option<&[u8]> {
         (self.ready_size != 0).then(|| &self.ready_buffer[..(self.ready_size)])
     }
 
-    /// If no bytes are received or first byte is invalid, returns `None`.
-    /// Otherwise, non-empty string will return.
+    /// If any line is ready, returns it decoded as UTF-8.
+    /// Invalid sequences are replaced with U+FFFD, which may make the text longer than the bytes.
+    /// Then the text is cut off before the first character not fitting in the buffer.
     pub fn ready_str(&self) -> Option<&str> {
         if self.ready_size == 0 {
             return None;
         }
 
-        let (s, _) = extract_valid_str(&self.ready_buffer[..(self.ready_size)]);
-        if s != "" {
-            Some(s)
-        } else {
-            None
-        }
+        // Only whole characters are written into buffer.
+        from_utf8(&self.ready_text[..(self.ready_decoded.text_size)]).ok()
+    }
+
+    /// Returns the number of invalid UTF-8 sequences in the ready line.
+    pub fn ready_errors(&self) -> usize {
+        self.ready_decoded.errors
+    }
+
+    /// Returns the first invalid UTF-8 sequence in the ready line.
+    /// The position is the offset in `ready_bytes`.
+    pub fn ready_first_error(&self) -> Option<Utf8DecodeError> {
+        self.ready_decoded.first_error
     }
 
     /// Clears line.
     pub fn clear(&mut self) {
         self.ready_size = 0;
+        self.ready_decoded = DecodedLine::EMPTY;
     }
 
     /// Polls to read new packet data.
@@ -58,6 +77,8 @@ impl<const BUFFER_SIZE: usize> LineReader<BUFFER_SIZE> {
                     ready_target.copy_from_slice(&self.ongoing_buffer[..(self.ongoing_written)]);
                     self.ready_size = self.ongoing_written;
                     self.ongoing_written = 0;
+                    let ready_bytes = &self.ready_buffer[..(self.ready_size)];
+                    self.ready_decoded = decode_line(ready_bytes, &mut self.ready_text);
                     ready_updated = true;
                 }
 
@@ -73,6 +94,53 @@ impl<const BUFFER_SIZE: usize> LineReader<BUFFER_SIZE> {
     }
 }
 
+/// Result of decoding a line by `decode_line`.
+#[derive(Debug, Clone, Copy)]
+struct DecodedLine {
+    text_size: usize,
+    errors: usize,
+    first_error: Option<Utf8DecodeError>,
+}
+
+impl DecodedLine {
+    const EMPTY: DecodedLine = DecodedLine {
+        text_size: 0,
+        errors: 0,
+        first_error: None,
+    };
+}
+
+/// Decodes complete line into `text`.
+fn decode_line(bytes: &[u8], text: &mut [u8]) -> DecodedLine {
+    let mut decoded_line = DecodedLine::EMPTY;
+    let mut truncated = false;
+    let mut push = |decoded: Result<char, Utf8DecodeError>| {
+        let c = decoded.unwrap_or_else(|error| {
+            decoded_line.errors += 1;
+            decoded_line.first_error.get_or_insert(error);
+            REPLACEMENT_CHAR
+        });
+        // Errors are still counted after the text is cut off.
+        let size = decoded_line.text_size;
+        if truncated || size + c.len_utf8() > text.len() {
+            truncated = true;
+        } else {
+            decoded_line.text_size += c.encode_utf8(&mut text[size..]).len();
+        }
+    };
+
+    let mut decoder = Utf8Decoder::new();
+    let mut decode = decoder.decode(bytes);
+    while let Some(decoded) = decode.next_checked() {
+        push(decoded);
+    }
+    // The line is complete, so an incomplete sequence at the end is invalid too.
+    if let Some(error) = decoder.finish_checked() {
+        push(Err(error));
+    }
+    decoded_line
+}
+
 /// Keeps bytes to write and manages the position.
 pub struct LineWriter<const BUFFER_SIZE: usize> {
     buffer: [u8; BUFFER_SIZE],
@@ -7,3 +7,4 @@ pub mod device;
 pub mod graphics;
 pub mod io;
 pub mod string;
+pub mod sync;
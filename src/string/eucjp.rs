@@ -0,0 +1,168 @@
+//! EUC-JP codec.
+
+use crate::string::{
+    encode_replacing, transliterate::find_with_fallback, write_decoded, EncodedChar,
+    Jis2UnicodeTable, Unicode2JisTable, REPLACEMENT_CHAR,
+};
+
+use core::fmt::{Result as FmtResult, Write};
+
+/// Single shift 2, prefix of halfwidth katakana.
+const SS2: u8 = 0x8E;
+
+/// Single shift 3, prefix of JIS X 0212.
+const SS3: u8 = 0x8F;
+
+/// Bytes waiting for the rest of sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    None,
+    Lead(u8),
+    SingleShift2,
+    SingleShift3,
+    SingleShift3Lead,
+}
+
+/// Decodes EUC-JP byte stream incrementally.
+/// An incomplete sequence at the end of input is kept until the next call.
+/// JIS X 0212 characters (prefixed by SS3) are decoded as U+FFFD.
+pub struct EucJpDecoder<'a> {
+    table: Jis2UnicodeTable<'a>,
+    pending: Pending,
+}
+
+impl<'a> EucJpDecoder<'a> {
+    /// Creates new decoder.
+    pub const fn new(table: Jis2UnicodeTable<'a>) -> EucJpDecoder<'a> {
+        EucJpDecoder {
+            table,
+            pending: Pending::None,
+        }
+    }
+
+    /// Decodes a chunk of bytes.
+    /// Invalid sequences and unmapped characters are decoded as U+FFFD.
+    pub fn decode<'d>(&'d mut self, bytes: &'d [u8]) -> EucJpDecode<'d, 'a> {
+        EucJpDecode {
+            decoder: self,
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes and writes them into `output` as UTF-8.
+    /// If `output` fails, the rest of the chunk is discarded.
+    pub fn decode_to(&mut self, bytes: &[u8], output: &mut impl Write) -> FmtResult {
+        write_decoded(self.decode(bytes), output)
+    }
+
+    /// Ends the stream.
+    /// If an incomplete sequence is left, U+FFFD will return.
+    pub fn finish(&mut self) -> Option<char> {
+        let pending = core::mem::replace(&mut self.pending, Pending::None);
+        (pending != Pending::None).then_some(REPLACEMENT_CHAR)
+    }
+
+    /// Whether an incomplete sequence is waiting for the rest.
+    pub fn is_pending(&self) -> bool {
+        self.pending != Pending::None
+    }
+}
+
+/// Iterator of characters returned by `EucJpDecoder::decode`.
+pub struct EucJpDecode<'d, 'a> {
+    decoder: &'d mut EucJpDecoder<'a>,
+    bytes: &'d [u8],
+    position: usize,
+}
+
+impl<'d, 'a> Iterator for EucJpDecode<'d, 'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            let &byte = self.bytes.get(self.position)?;
+            self.position += 1;
+
+            let pending = core::mem::replace(&mut self.decoder.pending, Pending::None);
+            let is_trail = (0xA1..=0xFE).contains(&byte);
+            match pending {
+                Pending::None => match byte {
+                    0x00..=0x7F => return Some(byte as char),
+                    SS2 => self.decoder.pending = Pending::SingleShift2,
+                    SS3 => self.decoder.pending = Pending::SingleShift3,
+                    0xA1..=0xFE => self.decoder.pending = Pending::Lead(byte),
+                    _ => return Some(REPLACEMENT_CHAR),
+                },
+                Pending::Lead(lead) if is_trail => {
+                    let kuten = (lead - 0xA0, byte - 0xA0);
+                    return Some(self.decoder.table.query(kuten).unwrap_or(REPLACEMENT_CHAR));
+                }
+                Pending::SingleShift2 if (0xA1..=0xDF).contains(&byte) => {
+                    return char::from_u32(0xFF61 + (byte - 0xA1) as u32);
+                }
+                Pending::SingleShift3 if is_trail => {
+                    self.decoder.pending = Pending::SingleShift3Lead;
+                }
+                Pending::SingleShift3Lead if is_trail => return Some(REPLACEMENT_CHAR),
+                _ => {
+                    // Byte which can start a sequence is not consumed as trail.
+                    if byte < 0x80 || is_trail || matches!(byte, SS2 | SS3) {
+                        self.position -= 1;
+                    }
+                    return Some(REPLACEMENT_CHAR);
+                }
+            }
+        }
+    }
+}
+
+/// Encodes string into EUC-JP.
+/// Characters missing from the table are retried with substitutes by `fallback_char`,
+/// unless fallback is disabled.
+pub struct EucJpEncoder<'a> {
+    table: Unicode2JisTable<'a>,
+    fallback: bool,
+}
+
+impl<'a> EucJpEncoder<'a> {
+    /// Creates new encoder.
+    pub const fn new(table: Unicode2JisTable<'a>) -> EucJpEncoder<'a> {
+        EucJpEncoder {
+            table,
+            fallback: true,
+        }
+    }
+
+    /// Enables or disables fallback.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Encodes a character.
+    /// Returns `None` if neither it nor its substitutes can be represented.
+    pub fn encode_char(&self, c: char) -> Option<EncodedChar> {
+        find_with_fallback(c, self.fallback, |s| self.encode_exact(s))
+    }
+
+    /// Encodes a character without substitution.
+    fn encode_exact(&self, c: char) -> Option<EncodedChar> {
+        match c as u32 {
+            0x00..=0x7F => Some(EncodedChar::new(&[c as u8])),
+            codepoint @ 0xFF61..=0xFF9F => {
+                Some(EncodedChar::new(&[SS2, (codepoint - 0xFF61) as u8 + 0xA1]))
+            }
+            _ => {
+                let (ku, ten) = self.table.query(c)?;
+                Some(EncodedChar::new(&[ku + 0xA0, ten + 0xA0]))
+            }
+        }
+    }
+
+    /// Encodes a string and passes bytes to `write_bytes` for each character.
+    /// Unrepresentable characters are replaced with `?`.
+    /// Returns the number of replaced characters.
+    pub fn encode(&self, text: &str, write_bytes: impl FnMut(&[u8])) -> usize {
+        encode_replacing(text, |c| self.encode_char(c), write_bytes)
+    }
+}
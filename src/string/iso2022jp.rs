@@ -0,0 +1,252 @@
+//! ISO-2022-JP codec.
+
+use crate::string::{
+    encode_replacing, transliterate::find_with_fallback, write_decoded, EncodedChar,
+    Jis2UnicodeTable, Unicode2JisTable, REPLACEMENT_CHAR,
+};
+
+use core::fmt::{Result as FmtResult, Write};
+
+/// Escape byte.
+const ESC: u8 = 0x1B;
+
+/// Character set designated by escape sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Iso2022JpCharset {
+    /// ASCII, designated by `ESC ( B`.
+    Ascii,
+
+    /// JIS X 0201 Roman, designated by `ESC ( J`.
+    Roman,
+
+    /// JIS X 0201 katakana, designated by `ESC ( I`. Accepted only by decoder.
+    Katakana,
+
+    /// JIS X 0208, designated by `ESC $ @` or `ESC $ B`.
+    Jis0208,
+}
+
+impl Iso2022JpCharset {
+    /// Returns the escape sequence designating this charset.
+    fn escape_sequence(self) -> &'static [u8] {
+        match self {
+            Iso2022JpCharset::Ascii => b"\x1B(B",
+            Iso2022JpCharset::Roman => b"\x1B(J",
+            Iso2022JpCharset::Katakana => b"\x1B(I",
+            Iso2022JpCharset::Jis0208 => b"\x1B$B",
+        }
+    }
+}
+
+/// Bytes waiting for the rest of sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pending {
+    None,
+    Escape,
+    EscapeIntermediate(u8),
+    Lead(u8),
+}
+
+/// Decodes ISO-2022-JP byte stream incrementally.
+/// Designated charset and incomplete sequences are kept across calls.
+pub struct Iso2022JpDecoder<'a> {
+    table: Jis2UnicodeTable<'a>,
+    charset: Iso2022JpCharset,
+    pending: Pending,
+}
+
+impl<'a> Iso2022JpDecoder<'a> {
+    /// Creates new decoder in ASCII state.
+    pub const fn new(table: Jis2UnicodeTable<'a>) -> Iso2022JpDecoder<'a> {
+        Iso2022JpDecoder {
+            table,
+            charset: Iso2022JpCharset::Ascii,
+            pending: Pending::None,
+        }
+    }
+
+    /// Returns currently designated charset.
+    pub fn charset(&self) -> Iso2022JpCharset {
+        self.charset
+    }
+
+    /// Decodes a chunk of bytes.
+    /// Invalid sequences and unmapped characters are decoded as U+FFFD.
+    pub fn decode<'d>(&'d mut self, bytes: &'d [u8]) -> Iso2022JpDecode<'d, 'a> {
+        Iso2022JpDecode {
+            decoder: self,
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes and writes them into `output` as UTF-8.
+    /// If `output` fails, the rest of the chunk is discarded.
+    pub fn decode_to(&mut self, bytes: &[u8], output: &mut impl Write) -> FmtResult {
+        write_decoded(self.decode(bytes), output)
+    }
+
+    /// Ends the stream and resets to ASCII state.
+    /// If an incomplete sequence is left, U+FFFD will return.
+    pub fn finish(&mut self) -> Option<char> {
+        self.charset = Iso2022JpCharset::Ascii;
+        let pending = core::mem::replace(&mut self.pending, Pending::None);
+        (pending != Pending::None).then_some(REPLACEMENT_CHAR)
+    }
+
+    /// Whether an incomplete sequence is waiting for the rest.
+    pub fn is_pending(&self) -> bool {
+        self.pending != Pending::None
+    }
+}
+
+/// Iterator of characters returned by `Iso2022JpDecoder::decode`.
+pub struct Iso2022JpDecode<'d, 'a> {
+    decoder: &'d mut Iso2022JpDecoder<'a>,
+    bytes: &'d [u8],
+    position: usize,
+}
+
+impl<'d, 'a> Iterator for Iso2022JpDecode<'d, 'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            let &byte = self.bytes.get(self.position)?;
+            self.position += 1;
+
+            let pending = core::mem::replace(&mut self.decoder.pending, Pending::None);
+            match (pending, byte) {
+                (Pending::Escape, b'(' | b'$') => {
+                    self.decoder.pending = Pending::EscapeIntermediate(byte);
+                }
+                (Pending::EscapeIntermediate(intermediate), _) => {
+                    let charset = match (intermediate, byte) {
+                        (b'(', b'B') => Iso2022JpCharset::Ascii,
+                        (b'(', b'J') => Iso2022JpCharset::Roman,
+                        (b'(', b'I') => Iso2022JpCharset::Katakana,
+                        (b'$', b'@' | b'B') => Iso2022JpCharset::Jis0208,
+                        _ => {
+                            self.position -= 1;
+                            return Some(REPLACEMENT_CHAR);
+                        }
+                    };
+                    self.decoder.charset = charset;
+                }
+                (Pending::Lead(lead), 0x21..=0x7E) => {
+                    let kuten = (lead - 0x20, byte - 0x20);
+                    return Some(self.decoder.table.query(kuten).unwrap_or(REPLACEMENT_CHAR));
+                }
+                (Pending::Escape | Pending::Lead(_), _) => {
+                    // The byte is not consumed as a part of sequence.
+                    self.position -= 1;
+                    return Some(REPLACEMENT_CHAR);
+                }
+
+                (Pending::None, ESC) => self.decoder.pending = Pending::Escape,
+                (Pending::None, 0x80..=0xFF) => return Some(REPLACEMENT_CHAR),
+                (Pending::None, 0x21..=0x7E) => match self.decoder.charset {
+                    Iso2022JpCharset::Ascii => return Some(byte as char),
+                    Iso2022JpCharset::Roman => {
+                        return Some(match byte {
+                            b'\\' => '\u{A5}',
+                            b'~' => '\u{203E}',
+                            _ => byte as char,
+                        })
+                    }
+                    Iso2022JpCharset::Katakana => {
+                        return match byte {
+                            0x21..=0x5F => char::from_u32(0xFF61 + (byte - 0x21) as u32),
+                            _ => Some(REPLACEMENT_CHAR),
+                        };
+                    }
+                    Iso2022JpCharset::Jis0208 => self.decoder.pending = Pending::Lead(byte),
+                },
+
+                // Controls and space pass through in any charset.
+                (Pending::None, _) => return Some(byte as char),
+            }
+        }
+    }
+}
+
+/// Encodes string into ISO-2022-JP.
+/// Only ASCII and JIS X 0208 are designated, as RFC 1468 allows.
+/// Characters missing from the table are retried with substitutes by `fallback_char`,
+/// such as halfwidth katakana with fullwidth ones, unless fallback is disabled.
+pub struct Iso2022JpEncoder<'a> {
+    table: Unicode2JisTable<'a>,
+    charset: Iso2022JpCharset,
+    fallback: bool,
+}
+
+impl<'a> Iso2022JpEncoder<'a> {
+    /// Creates new encoder in ASCII state.
+    pub const fn new(table: Unicode2JisTable<'a>) -> Iso2022JpEncoder<'a> {
+        Iso2022JpEncoder {
+            table,
+            charset: Iso2022JpCharset::Ascii,
+            fallback: true,
+        }
+    }
+
+    /// Enables or disables fallback.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Encodes a character, preceded by escape sequence if charset changes.
+    /// Returns `None` if neither it nor its substitutes can be represented;
+    /// the state is not changed then.
+    pub fn encode_char(&mut self, c: char) -> Option<EncodedChar> {
+        find_with_fallback(c, self.fallback, |s| self.encode_exact(s))
+    }
+
+    /// Encodes a character without substitution.
+    fn encode_exact(&mut self, c: char) -> Option<EncodedChar> {
+        let (charset, bytes) = match c as u32 {
+            0x00..=0x7F => (Iso2022JpCharset::Ascii, [c as u8, 0]),
+            _ => {
+                let (ku, ten) = self.table.query(c)?;
+                (Iso2022JpCharset::Jis0208, [ku + 0x20, ten + 0x20])
+            }
+        };
+        let bytes = match charset {
+            Iso2022JpCharset::Ascii => &bytes[..1],
+            _ => &bytes[..],
+        };
+
+        let mut buffer = [0; 5];
+        let escape = if charset != self.charset {
+            self.charset = charset;
+            charset.escape_sequence()
+        } else {
+            &[]
+        };
+        buffer[..escape.len()].copy_from_slice(escape);
+        buffer[escape.len()..(escape.len() + bytes.len())].copy_from_slice(bytes);
+        Some(EncodedChar::new(&buffer[..(escape.len() + bytes.len())]))
+    }
+
+    /// Ends the text and returns escape sequence back to ASCII if needed.
+    pub fn finish(&mut self) -> Option<EncodedChar> {
+        if self.charset == Iso2022JpCharset::Ascii {
+            return None;
+        }
+
+        self.charset = Iso2022JpCharset::Ascii;
+        Some(EncodedChar::new(Iso2022JpCharset::Ascii.escape_sequence()))
+    }
+
+    /// Encodes a whole string and passes bytes to `write_bytes` for each character.
+    /// The output always ends in ASCII state.
+    /// Unrepresentable characters are replaced with `?`.
+    /// Returns the number of replaced characters.
+    pub fn encode(&mut self, text: &str, mut write_bytes: impl FnMut(&[u8])) -> usize {
+        let replaced = encode_replacing(text, |c| self.encode_char(c), &mut write_bytes);
+        if let Some(reset) = self.finish() {
+            write_bytes(reset.as_bytes());
+        }
+        replaced
+    }
+}
@@ -0,0 +1,193 @@
+//! Kana and width conversion.
+
+use core::{
+    fmt::{Result as FmtResult, Write},
+    iter::Peekable,
+};
+
+/// Fullwidth forms of halfwidth katakana block U+FF61..=U+FF9F.
+const FULLWIDTH_KATAKANA: &str =
+    "。「」、・ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン\u{309B}\u{309C}";
+
+/// Kana which have voiced forms, and the voiced forms in the same order.
+const VOICED_BASES: &str = "うかきくけこさしすせそたちつてとはひふへほウカキクケコサシスセソタチツテトハヒフヘホワヰヱヲゝヽ";
+const VOICED_FORMS: &str = "ゔがぎぐげござじずぜぞだぢづでどばびぶべぼヴガギグゲゴザジズゼゾダヂヅデドバビブベボヷヸヹヺゞヾ";
+
+/// Kana which have semi-voiced forms, and the semi-voiced forms in the same order.
+const SEMI_VOICED_BASES: &str = "はひふへほハヒフヘホ";
+const SEMI_VOICED_FORMS: &str = "ぱぴぷぺぽパピプペポ";
+
+/// Looks up `c` in `from` and returns the character at the same position in `to`.
+pub(crate) fn translate(c: char, from: &str, to: &str) -> Option<char> {
+    let position = from.chars().position(|f| f == c)?;
+    to.chars().nth(position)
+}
+
+/// Converts halfwidth katakana and punctuation into fullwidth.
+/// Halfwidth sound marks become spacing marks U+309B and U+309C.
+pub fn halfwidth_to_fullwidth_katakana(c: char) -> Option<char> {
+    let index = (c as u32).checked_sub(0xFF61)?;
+    FULLWIDTH_KATAKANA.chars().nth(index as usize)
+}
+
+/// Converts fullwidth katakana and punctuation into halfwidth.
+/// Voiced and semi-voiced katakana are decomposed into base and halfwidth sound mark.
+pub fn fullwidth_to_halfwidth_katakana(c: char) -> Option<(char, Option<char>)> {
+    let halfwidth = |c| {
+        let index = match c {
+            '\u{3099}' => 0x3D,
+            '\u{309A}' => 0x3E,
+            _ => FULLWIDTH_KATAKANA.chars().position(|f| f == c)?,
+        };
+        char::from_u32(0xFF61 + index as u32)
+    };
+    if let Some(base) = translate(c, VOICED_FORMS, VOICED_BASES) {
+        Some((halfwidth(base)?, Some('\u{FF9E}')))
+    } else if let Some(base) = translate(c, SEMI_VOICED_FORMS, SEMI_VOICED_BASES) {
+        Some((halfwidth(base)?, Some('\u{FF9F}')))
+    } else {
+        Some((halfwidth(c)?, None))
+    }
+}
+
+/// Converts hiragana into katakana.
+pub fn hiragana_to_katakana(c: char) -> Option<char> {
+    match c {
+        '\u{3041}'..='\u{3096}' | '\u{309D}'..='\u{309E}' => char::from_u32(c as u32 + 0x60),
+        _ => None,
+    }
+}
+
+/// Converts katakana into hiragana.
+/// Katakana without hiragana counterpart, such as `ヷ`, are not converted.
+pub fn katakana_to_hiragana(c: char) -> Option<char> {
+    match c {
+        '\u{30A1}'..='\u{30F6}' | '\u{30FD}'..='\u{30FE}' => char::from_u32(c as u32 - 0x60),
+        _ => None,
+    }
+}
+
+/// Converts ASCII graphic characters and space into fullwidth forms.
+pub fn ascii_to_fullwidth(c: char) -> Option<char> {
+    match c {
+        ' ' => Some('\u{3000}'),
+        '!'..='~' => char::from_u32(c as u32 + 0xFEE0),
+        _ => None,
+    }
+}
+
+/// Converts fullwidth forms of ASCII graphic characters and ideographic space into ASCII.
+pub fn fullwidth_to_ascii(c: char) -> Option<char> {
+    match c {
+        '\u{3000}' => Some(' '),
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0),
+        _ => None,
+    }
+}
+
+/// Returns voiced form of kana, such as `が` for `か`.
+pub fn voiced(c: char) -> Option<char> {
+    translate(c, VOICED_BASES, VOICED_FORMS)
+}
+
+/// Returns semi-voiced form of kana, such as `ぱ` for `は`.
+pub fn semi_voiced(c: char) -> Option<char> {
+    translate(c, SEMI_VOICED_BASES, SEMI_VOICED_FORMS)
+}
+
+/// Composes kana and following sound mark into single character.
+/// Combining (U+3099, U+309A) and halfwidth (U+FF9E, U+FF9F) marks are accepted,
+/// and halfwidth katakana is composed as fullwidth.
+pub fn compose_sound_mark(base: char, mark: char) -> Option<char> {
+    let base = halfwidth_to_fullwidth_katakana(base).unwrap_or(base);
+    match mark {
+        '\u{3099}' | '\u{FF9E}' => voiced(base),
+        '\u{309A}' | '\u{FF9F}' => semi_voiced(base),
+        _ => None,
+    }
+}
+
+/// Conversion applied by `KanaConvert`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KanaConversion {
+    /// Hiragana into katakana.
+    HiraganaToKatakana,
+
+    /// Katakana into hiragana.
+    KatakanaToHiragana,
+
+    /// Halfwidth katakana into fullwidth, composing halfwidth sound marks.
+    HalfwidthToFullwidth,
+
+    /// Fullwidth katakana into halfwidth, decomposing sound marks.
+    FullwidthToHalfwidth,
+
+    /// Fullwidth forms into ASCII.
+    FullwidthToAscii,
+
+    /// ASCII into fullwidth forms.
+    AsciiToFullwidth,
+}
+
+/// Iterator which converts characters by `KanaConversion`.
+/// Characters not subject to the conversion are passed through.
+pub struct KanaConvert<I: Iterator<Item = char>> {
+    chars: Peekable<I>,
+    conversion: KanaConversion,
+    pending: Option<char>,
+}
+
+impl<I: Iterator<Item = char>> KanaConvert<I> {
+    /// Wraps characters.
+    pub fn new(chars: I, conversion: KanaConversion) -> KanaConvert<I> {
+        KanaConvert {
+            chars: chars.peekable(),
+            conversion,
+            pending: None,
+        }
+    }
+}
+
+impl<I: Iterator<Item = char>> Iterator for KanaConvert<I> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if let Some(pending) = self.pending.take() {
+            return Some(pending);
+        }
+
+        let c = self.chars.next()?;
+        let converted = match self.conversion {
+            KanaConversion::HiraganaToKatakana => hiragana_to_katakana(c),
+            KanaConversion::KatakanaToHiragana => katakana_to_hiragana(c),
+            KanaConversion::HalfwidthToFullwidth => {
+                let composed = self
+                    .chars
+                    .peek()
+                    .filter(|&&m| m == '\u{FF9E}' || m == '\u{FF9F}')
+                    .and_then(|&m| compose_sound_mark(c, m));
+                if composed.is_some() {
+                    self.chars.next();
+                }
+                composed.or_else(|| halfwidth_to_fullwidth_katakana(c))
+            }
+            KanaConversion::FullwidthToHalfwidth => {
+                fullwidth_to_halfwidth_katakana(c).map(|(base, mark)| {
+                    self.pending = mark;
+                    base
+                })
+            }
+            KanaConversion::FullwidthToAscii => fullwidth_to_ascii(c),
+            KanaConversion::AsciiToFullwidth => ascii_to_fullwidth(c),
+        };
+        Some(converted.unwrap_or(c))
+    }
+}
+
+/// Converts string by `KanaConversion` and writes into `output`, such as `FormatBuffer`.
+pub fn convert_to(s: &str, conversion: KanaConversion, output: &mut impl Write) -> FmtResult {
+    for c in KanaConvert::new(s.chars(), conversion) {
+        output.write_char(c)?;
+    }
+    Ok(())
+}
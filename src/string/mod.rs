@@ -0,0 +1,530 @@
+//! Contains string manipulation.
+
+pub mod eucjp;
+pub mod iso2022jp;
+pub mod kana;
+pub mod romaji;
+pub mod sjis;
+pub mod transliterate;
+pub mod utf8;
+pub mod width;
+
+use core::{
+    cmp::Ordering,
+    fmt::{Error as FmtError, Result as FmtResult, Write},
+    marker::PhantomData,
+    str::{from_utf8, from_utf8_unchecked},
+};
+
+/// Width for kuten (row and cell) pages of 94x94 double-byte charsets.
+pub const KUTEN_WIDTH: usize = 94;
+
+/// Width for JIS kuten pages.
+pub const JIS_KUTEN_WIDTH: usize = KUTEN_WIDTH;
+
+/// Conversion table binary of Unicode codepoint to JIS kuten code.
+/// Assets of conversion tables are generated by `tools/uni2jis-gen`.
+pub const UNI2JIS_DATA: &[u8] = include_bytes!("../../assets/uni2jis.bin");
+
+/// Conversion table binary of Unicode codepoint to JIS X 0213 kuten code.
+/// Plane 2 rows are numbered after plane 1; see `menkuten_to_kuten`.
+pub const UNI2JIS0213_DATA: &[u8] = include_bytes!("../../assets/uni2jis0213.bin");
+
+/// Buffer to use with core::fmt functions.
+pub struct FormatBuffer<const BUFFER_SIZE: usize> {
+    buffer: [u8; BUFFER_SIZE],
+    written: usize,
+}
+
+impl<const BUFFER_SIZE: usize> FormatBuffer<BUFFER_SIZE> {
+    /// Initializes with constant size.
+    pub const fn new() -> FormatBuffer<BUFFER_SIZE> {
+        FormatBuffer {
+            buffer: [0; BUFFER_SIZE],
+            written: 0,
+        }
+    }
+
+    /// Returns written bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.buffer[..self.written]
+    }
+
+    /// Returns valid part as string.
+    pub fn valid_str(&self) -> &str {
+        let (valid, _) = extract_valid_str(&self.buffer[..self.written]);
+        valid
+    }
+
+    /// Clears buffer content.
+    pub fn clear(&mut self) {
+        self.written = 0;
+    }
+}
+
+impl<const BUFFER_SIZE: usize> Write for FormatBuffer<BUFFER_SIZE> {
+    fn write_str(&mut self, s: &str) -> FmtResult {
+        let str_bytes = s.as_bytes();
+        let str_length = s.len();
+
+        let buffer_left = BUFFER_SIZE - self.written;
+        if str_length > buffer_left {
+            return Err(FmtError);
+        }
+
+        let target = &mut self.buffer[(self.written)..(self.written + str_length)];
+        target.copy_from_slice(str_bytes);
+        self.written += str_length;
+        Ok(())
+    }
+}
+
+/// Double-byte charset laid out in 94x94 kuten grid.
+pub trait Charset94 {
+    /// Built-in Unicode to kuten table binary, if any.
+    const UNI2KUTEN_DATA: Option<&'static [u8]>;
+
+    /// Pairs of base and combining character which have their own kuten code.
+    const SEQUENCES: &'static [CombiningSequence] = &[];
+}
+
+/// Character sequence mapped to single kuten code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CombiningSequence {
+    pub base: char,
+    pub combining: char,
+    pub kuten: (u8, u8),
+}
+
+impl CombiningSequence {
+    const fn new(base: char, combining: char, kuten: (u8, u8)) -> CombiningSequence {
+        CombiningSequence {
+            base,
+            combining,
+            kuten,
+        }
+    }
+}
+
+/// JIS X 0208.
+#[derive(Debug, Clone, Copy)]
+pub enum Jis0208 {}
+
+impl Charset94 for Jis0208 {
+    const UNI2KUTEN_DATA: Option<&'static [u8]> = Some(UNI2JIS_DATA);
+}
+
+/// JIS X 0213 plane 1 and 2.
+/// Kuten codes of plane 2 continue from plane 1, as ku `95..=188`.
+#[derive(Debug, Clone, Copy)]
+pub enum Jis0213 {}
+
+impl Charset94 for Jis0213 {
+    const UNI2KUTEN_DATA: Option<&'static [u8]> = Some(UNI2JIS0213_DATA);
+    const SEQUENCES: &'static [CombiningSequence] = &JIS0213_SEQUENCES;
+}
+
+/// Combining sequences in JIS X 0213 plane 1.
+const JIS0213_SEQUENCES: [CombiningSequence; 25] = [
+    CombiningSequence::new('\u{304B}', '\u{309A}', (4, 87)),
+    CombiningSequence::new('\u{304D}', '\u{309A}', (4, 88)),
+    CombiningSequence::new('\u{304F}', '\u{309A}', (4, 89)),
+    CombiningSequence::new('\u{3051}', '\u{309A}', (4, 90)),
+    CombiningSequence::new('\u{3053}', '\u{309A}', (4, 91)),
+    CombiningSequence::new('\u{30AB}', '\u{309A}', (5, 87)),
+    CombiningSequence::new('\u{30AD}', '\u{309A}', (5, 88)),
+    CombiningSequence::new('\u{30AF}', '\u{309A}', (5, 89)),
+    CombiningSequence::new('\u{30B1}', '\u{309A}', (5, 90)),
+    CombiningSequence::new('\u{30B3}', '\u{309A}', (5, 91)),
+    CombiningSequence::new('\u{30BB}', '\u{309A}', (5, 92)),
+    CombiningSequence::new('\u{30C4}', '\u{309A}', (5, 93)),
+    CombiningSequence::new('\u{30C8}', '\u{309A}', (5, 94)),
+    CombiningSequence::new('\u{31F7}', '\u{309A}', (6, 88)),
+    CombiningSequence::new('\u{00E6}', '\u{0300}', (11, 36)),
+    CombiningSequence::new('\u{0254}', '\u{0300}', (11, 40)),
+    CombiningSequence::new('\u{0254}', '\u{0301}', (11, 41)),
+    CombiningSequence::new('\u{028C}', '\u{0300}', (11, 42)),
+    CombiningSequence::new('\u{028C}', '\u{0301}', (11, 43)),
+    CombiningSequence::new('\u{0259}', '\u{0300}', (11, 44)),
+    CombiningSequence::new('\u{0259}', '\u{0301}', (11, 45)),
+    CombiningSequence::new('\u{025A}', '\u{0300}', (11, 46)),
+    CombiningSequence::new('\u{025A}', '\u{0301}', (11, 47)),
+    CombiningSequence::new('\u{02E9}', '\u{02E5}', (11, 69)),
+    CombiningSequence::new('\u{02E5}', '\u{02E9}', (11, 70)),
+];
+
+/// Converts JIS X 0213 men-ku-ten code into kuten code of `Jis0213`.
+/// Returns `None` if any of them is out of range.
+pub fn menkuten_to_kuten(men: u8, ku: u8, ten: u8) -> Option<(u8, u8)> {
+    let range = 1..=(KUTEN_WIDTH as u8);
+    if !(1..=2).contains(&men) || !range.contains(&ku) || !range.contains(&ten) {
+        return None;
+    }
+    Some(((men - 1) * KUTEN_WIDTH as u8 + ku, ten))
+}
+
+/// Converts kuten code of `Jis0213` into JIS X 0213 men-ku-ten code.
+/// Returns `None` if ku is not in `1..=188` or ten is not in `1..=94`.
+pub fn kuten_to_menkuten((ku, ten): (u8, u8)) -> Option<(u8, u8, u8)> {
+    let width = KUTEN_WIDTH as u8;
+    if !(1..=(width * 2)).contains(&ku) || !(1..=width).contains(&ten) {
+        return None;
+    }
+    let plane_index = (ku - 1) / width;
+    Some((plane_index + 1, ku - plane_index * width, ten))
+}
+
+/// GB 2312.
+#[derive(Debug, Clone, Copy)]
+pub enum Gb2312 {}
+
+impl Charset94 for Gb2312 {
+    const UNI2KUTEN_DATA: Option<&'static [u8]> = None;
+}
+
+/// KS X 1001.
+#[derive(Debug, Clone, Copy)]
+pub enum Ksx1001 {}
+
+impl Charset94 for Ksx1001 {
+    const UNI2KUTEN_DATA: Option<&'static [u8]> = None;
+}
+
+/// Converts Unicode codepoint to JIS kuten code.
+pub type Unicode2JisTable<'a> = Unicode2KutenTable<'a, Jis0208>;
+
+/// Converts Unicode codepoint to JIS X 0213 kuten code.
+pub type Unicode2Jis0213Table<'a> = Unicode2KutenTable<'a, Jis0213>;
+
+/// Converts Unicode codepoint to kuten code of charset `S`.
+///
+/// Three table formats are accepted. All multibyte values are little endian.
+/// - Version 1 (BMP only):
+///   `chain_length: u16`, `elements_count: u16`,
+///   chain indices `[u16; 0x10000 / chain_length]`, elements `[(u16, u8, u8); elements_count]`.
+/// - Version 2 (full range): `0u16`, `2u16`, `chain_length: u16`, `elements_count: u16`,
+///   `astral_count: u16`, then version 1 chain indices and elements for BMP,
+///   followed by sorted astral elements `[(u24, u8, u8); astral_count]`.
+/// - Version 3 (blocked): `0u16`, `3u16`, `chain_length: u16`, `elements_count: u16`,
+///   `astral_count: u16`, chain indices `[u16; 0x10000 / chain_length + 1]` ending with
+///   `elements_count`, codepoints `[u16; elements_count]`, kuten `[(u8, u8); elements_count]`,
+///   then astral elements as version 2.
+///   Each chain is binary-searched instead of scanned linearly.
+#[derive(Clone, Copy)]
+pub struct Unicode2KutenTable<'a, S> {
+    chain_indices: &'a [u8],
+    table_elements: &'a [u8],
+    astral_elements: &'a [u8],
+    chain_length_bit: u32,
+    elements_count: usize,
+    blocked: bool,
+    charset: PhantomData<fn() -> S>,
+}
+
+impl<'a, S> Unicode2KutenTable<'a, S>
+where
+    S: Charset94,
+{
+    /// Constructs table by referencing byte slice.
+    /// If the header information does not match the whole table size, `Err(_)` will return.
+    pub const fn new(
+        table_bytes: &'a [u8],
+    ) -> Result<Unicode2KutenTable<'a, S>, Uni2JisTableError> {
+        if table_bytes.len() < 4 {
+            return Err(Uni2JisTableError::InsufficientSize);
+        }
+
+        // Version 1 never has zero chain length, so it marks versioned header.
+        let first_word = u16::from_le_bytes([table_bytes[0], table_bytes[1]]);
+        let (header_size, chain_length, elements_count, astral_count, blocked) = match first_word {
+            0 => {
+                let version = u16::from_le_bytes([table_bytes[2], table_bytes[3]]);
+                if version != 2 && version != 3 {
+                    return Err(Uni2JisTableError::UnsupportedVersion);
+                } else if table_bytes.len() < 10 {
+                    return Err(Uni2JisTableError::InsufficientSize);
+                }
+                (
+                    10,
+                    u16::from_le_bytes([table_bytes[4], table_bytes[5]]),
+                    u16::from_le_bytes([table_bytes[6], table_bytes[7]]),
+                    u16::from_le_bytes([table_bytes[8], table_bytes[9]]) as usize,
+                    version == 3,
+                )
+            }
+            _ => {
+                let elements_count = u16::from_le_bytes([table_bytes[2], table_bytes[3]]);
+                (4, first_word, elements_count, 0, false)
+            }
+        };
+        if !chain_length.is_power_of_two() {
+            return Err(Uni2JisTableError::IncorrectData);
+        }
+
+        let elements_count = elements_count as usize;
+        let chain_length_bit = chain_length.trailing_zeros();
+        let chains_count: usize = (0x10000 >> chain_length_bit) + blocked as usize;
+
+        let expected_size =
+            header_size + (chains_count * 2) + (elements_count * 4) + (astral_count * 5);
+        if table_bytes.len() != expected_size {
+            return Err(Uni2JisTableError::IncorrectData);
+        }
+
+        let (_, table_body) = table_bytes.split_at(header_size);
+        let (chain_indices, table_body) = table_body.split_at(chains_count * 2);
+        let (table_elements, astral_elements) = table_body.split_at(elements_count * 4);
+
+        // Blocked format slices elements by chain indices, so they must be in order.
+        if blocked {
+            let mut previous = 0;
+            let mut chain = 0;
+            while chain < chains_count {
+                let index =
+                    u16::from_le_bytes([chain_indices[chain * 2], chain_indices[chain * 2 + 1]]);
+                if (index as usize) < previous || index as usize > elements_count {
+                    return Err(Uni2JisTableError::IncorrectData);
+                }
+                previous = index as usize;
+                chain += 1;
+            }
+            if previous != elements_count {
+                return Err(Uni2JisTableError::IncorrectData);
+            }
+        }
+
+        Ok(Unicode2KutenTable {
+            chain_indices,
+            table_elements,
+            astral_elements,
+            chain_length_bit,
+            elements_count,
+            blocked,
+            charset: PhantomData,
+        })
+    }
+
+    /// Queries Unicode character.
+    /// Characters outside BMP are rejected unless the table has astral elements for them.
+    pub fn query(&self, c: char) -> Option<(u8, u8)> {
+        match u16::try_from(c as u32) {
+            Ok(c) if self.blocked => self.query_blocked(c),
+            Ok(c) => self.query_bmp(c),
+            Err(_) => self.query_astral(c as u32),
+        }
+    }
+
+    /// Queries pair of base and combining character.
+    /// Returns `None` unless `S` has a kuten code for the sequence.
+    pub fn query_sequence(&self, base: char, combining: char) -> Option<(u8, u8)> {
+        S::SEQUENCES
+            .iter()
+            .find(|s| s.base == base && s.combining == combining)
+            .map(|s| s.kuten)
+    }
+
+    /// Searches chained elements.
+    fn query_bmp(&self, c: u16) -> Option<(u8, u8)> {
+        let chain = (c as u32 >> self.chain_length_bit) as usize;
+        let chain_start = u16::from_le_bytes([
+            self.chain_indices[chain * 2],
+            self.chain_indices[chain * 2 + 1],
+        ]) as usize;
+        let chain_end = (chain_start + (1 << self.chain_length_bit)).min(self.elements_count);
+        for element_index in chain_start..chain_end {
+            let element = &self.table_elements[(element_index * 4)..((element_index + 1) * 4)];
+            let element_char = u16::from_le_bytes([element[0], element[1]]);
+            match c.cmp(&element_char) {
+                Ordering::Greater => continue,
+                Ordering::Equal => return Some((element[2], element[3])),
+                Ordering::Less => return None,
+            }
+        }
+        None
+    }
+
+    /// Binary-searches codepoints in the chain of blocked format.
+    fn query_blocked(&self, c: u16) -> Option<(u8, u8)> {
+        let chain = (c as u32 >> self.chain_length_bit) as usize;
+        let chain_index = |i: usize| {
+            u16::from_le_bytes([self.chain_indices[i * 2], self.chain_indices[i * 2 + 1]]) as usize
+        };
+        let (mut low, mut high) = (chain_index(chain), chain_index(chain + 1));
+        while low < high {
+            let middle = (low + high) / 2;
+            let element_char = u16::from_le_bytes([
+                self.table_elements[middle * 2],
+                self.table_elements[middle * 2 + 1],
+            ]);
+            match c.cmp(&element_char) {
+                Ordering::Greater => low = middle + 1,
+                Ordering::Equal => {
+                    let kuten_index = (self.elements_count + middle) * 2;
+                    let kuten = &self.table_elements[kuten_index..(kuten_index + 2)];
+                    return Some((kuten[0], kuten[1]));
+                }
+                Ordering::Less => high = middle,
+            }
+        }
+        None
+    }
+
+    /// Binary-searches astral elements.
+    fn query_astral(&self, c: u32) -> Option<(u8, u8)> {
+        let (mut low, mut high) = (0, self.astral_elements.len() / 5);
+        while low < high {
+            let middle = (low + high) / 2;
+            let element = &self.astral_elements[(middle * 5)..((middle + 1) * 5)];
+            let element_char = u32::from_le_bytes([element[0], element[1], element[2], 0]);
+            match c.cmp(&element_char) {
+                Ordering::Greater => low = middle + 1,
+                Ordering::Equal => return Some((element[3], element[4])),
+                Ordering::Less => high = middle,
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Uni2JisTableError {
+    InsufficientSize,
+    IncorrectData,
+    UnsupportedVersion,
+}
+
+/// Replacement character for undecodable bytes.
+pub(crate) const REPLACEMENT_CHAR: char = '\u{FFFD}';
+
+/// Writes decoded characters into `output`.
+/// If `output` fails, the rest of the characters are discarded.
+pub(crate) fn write_decoded(
+    chars: impl Iterator<Item = char>,
+    output: &mut impl Write,
+) -> FmtResult {
+    for c in chars {
+        output.write_char(c)?;
+    }
+    Ok(())
+}
+
+/// Encodes a string and passes bytes to `write_bytes` for each character.
+/// Unrepresentable characters are replaced with `?`.
+/// Returns the number of replaced characters.
+pub(crate) fn encode_replacing(
+    text: &str,
+    mut encode_char: impl FnMut(char) -> Option<EncodedChar>,
+    mut write_bytes: impl FnMut(&[u8]),
+) -> usize {
+    let mut replaced = 0;
+    for c in text.chars() {
+        let encoded = encode_char(c).unwrap_or_else(|| {
+            replaced += 1;
+            encode_char('?').expect("ASCII is always encodable")
+        });
+        write_bytes(encoded.as_bytes());
+    }
+    replaced
+}
+
+/// Bytes of a character in legacy encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodedChar {
+    bytes: [u8; 8],
+    length: usize,
+}
+
+impl EncodedChar {
+    /// Constructs from byte slice up to 8 bytes.
+    pub(crate) fn new(source: &[u8]) -> EncodedChar {
+        let mut bytes = [0; 8];
+        bytes[..source.len()].copy_from_slice(source);
+        EncodedChar {
+            bytes,
+            length: source.len(),
+        }
+    }
+
+    /// Returns encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.length]
+    }
+}
+
+/// Conversion table binary of JIS kuten code to Unicode codepoint.
+/// NEC cells duplicating JIS row 2 are left empty, so that it round trips with `UNI2JIS_DATA`.
+pub const JIS2UNI_DATA: &[u8] = include_bytes!("../../assets/jis2uni.bin");
+
+/// Converts JIS kuten code to Unicode codepoint.
+pub type Jis2UnicodeTable<'a> = Kuten2UnicodeTable<'a, Jis0208>;
+
+/// Converts kuten code of charset `S` to Unicode codepoint.
+///
+/// The table is dense `KUTEN_WIDTH` x `KUTEN_WIDTH` array of 24-bit little endian codepoints
+/// in kuten order. Zero means unmapped.
+#[derive(Clone, Copy)]
+pub struct Kuten2UnicodeTable<'a, S> {
+    table_elements: &'a [u8],
+    charset: PhantomData<fn() -> S>,
+}
+
+impl<'a, S> Kuten2UnicodeTable<'a, S>
+where
+    S: Charset94,
+{
+    /// Constructs table by referencing byte slice.
+    /// If the size does not match, `Err(_)` will return.
+    pub const fn new(
+        table_bytes: &'a [u8],
+    ) -> Result<Kuten2UnicodeTable<'a, S>, Jis2UniTableError> {
+        if table_bytes.len() != KUTEN_WIDTH * KUTEN_WIDTH * 3 {
+            return Err(Jis2UniTableError::IncorrectSize);
+        }
+
+        Ok(Kuten2UnicodeTable {
+            table_elements: table_bytes,
+            charset: PhantomData,
+        })
+    }
+
+    /// Queries JIS kuten code.
+    /// Both ku and ten are 1-based.
+    pub fn query(&self, (ku, ten): (u8, u8)) -> Option<char> {
+        if !(1..=KUTEN_WIDTH).contains(&(ku as usize))
+            || !(1..=KUTEN_WIDTH).contains(&(ten as usize))
+        {
+            return None;
+        }
+
+        let index = (ku as usize - 1) * KUTEN_WIDTH + (ten as usize - 1);
+        let element = &self.table_elements[(index * 3)..((index + 1) * 3)];
+        match u32::from_le_bytes([element[0], element[1], element[2], 0]) {
+            0 => None,
+            codepoint => char::from_u32(codepoint),
+        }
+    }
+
+    /// Iterates over mapped characters in the ku (row) with their ten.
+    pub fn row(&self, ku: u8) -> impl Iterator<Item = (u8, char)> + '_ {
+        (1..=(KUTEN_WIDTH as u8)).filter_map(move |ten| Some((ten, self.query((ku, ten))?)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jis2UniTableError {
+    IncorrectSize,
+}
+
+/// Splits input slice into two part: valid UTF-8 string from beginning, and the rest.
+pub fn extract_valid_str(source: &[u8]) -> (&str, &[u8]) {
+    match from_utf8(source) {
+        Ok(s) => (s, &[]),
+        Err(err) => {
+            // The former is guaranteed to be valid UTF-8.
+            let (valid, rest) = source.split_at(err.valid_up_to());
+            let valid_str = unsafe { from_utf8_unchecked(valid) };
+
+            (valid_str, rest)
+        }
+    }
+}
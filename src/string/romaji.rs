@@ -0,0 +1,259 @@
+//! Romaji to kana input conversion.
+
+use crate::string::kana::hiragana_to_katakana;
+
+use core::{
+    fmt::{Result as FmtResult, Write},
+    str::from_utf8,
+};
+
+/// Longest romaji in the table.
+const MAX_ROMAJI_LENGTH: usize = 4;
+
+/// Romaji in Hepburn and kunrei-shiki, and kana in the same order, separated by spaces.
+const ROMAJI: &str = concat!(
+    "a i u e o ",
+    "ka ki ku ke ko kya kyu kyo ",
+    "ga gi gu ge go gya gyu gyo ",
+    "sa si shi su se so sya sha syu shu sye she syo sho ",
+    "za zi ji zu ze zo zya jya ja zyu jyu ju zye jye je zyo jyo jo ",
+    "ta ti chi tu tsu te to tya cha cya tyu chu cyu tye che cye tyo cho cyo tsa thi twu ",
+    "da di du de do dya dyu dyo dhi dwu ",
+    "na ni nu ne no nya nyu nyo n' ",
+    "ha hi hu fu he ho hya hyu hyo fa fi fe fo fyu ",
+    "ba bi bu be bo bya byu byo ",
+    "pa pi pu pe po pya pyu pyo ",
+    "ma mi mu me mo mya myu myo ",
+    "ya yu ye yo ",
+    "ra ri ru re ro rya ryu ryo ",
+    "wa wi we wo wyi wye ",
+    "va vi vu ve vo ",
+    "xa xi xu xe xo xya xyu xyo xtu xtsu xwa xka xke ",
+    "la li lu le lo lya lyu lyo ltu ltsu lwa lka lke ",
+    "- ",
+);
+const KANA: &str = concat!(
+    "あ い う え お ",
+    "か き く け こ きゃ きゅ きょ ",
+    "が ぎ ぐ げ ご ぎゃ ぎゅ ぎょ ",
+    "さ し し す せ そ しゃ しゃ しゅ しゅ しぇ しぇ しょ しょ ",
+    "ざ じ じ ず ぜ ぞ じゃ じゃ じゃ じゅ じゅ じゅ じぇ じぇ じぇ じょ じょ じょ ",
+    "た ち ち つ つ て と ちゃ ちゃ ちゃ ちゅ ちゅ ちゅ ちぇ ちぇ ちぇ ちょ ちょ ちょ つぁ てぃ とぅ ",
+    "だ ぢ づ で ど ぢゃ ぢゅ ぢょ でぃ どぅ ",
+    "な に ぬ ね の にゃ にゅ にょ ん ",
+    "は ひ ふ ふ へ ほ ひゃ ひゅ ひょ ふぁ ふぃ ふぇ ふぉ ふゅ ",
+    "ば び ぶ べ ぼ びゃ びゅ びょ ",
+    "ぱ ぴ ぷ ぺ ぽ ぴゃ ぴゅ ぴょ ",
+    "ま み む め も みゃ みゅ みょ ",
+    "や ゆ いぇ よ ",
+    "ら り る れ ろ りゃ りゅ りょ ",
+    "わ うぃ うぇ を ゐ ゑ ",
+    "ゔぁ ゔぃ ゔ ゔぇ ゔぉ ",
+    "ぁ ぃ ぅ ぇ ぉ ゃ ゅ ょ っ っ ゎ ゕ ゖ ",
+    "ぁ ぃ ぅ ぇ ぉ ゃ ゅ ょ っ っ ゎ ゕ ゖ ",
+    "ー ",
+);
+
+/// Kana script to output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KanaScript {
+    /// Hiragana, for words and names.
+    Hiragana,
+
+    /// Katakana, for loanwords and labels.
+    Katakana,
+}
+
+/// Characters output by a keystroke.
+#[derive(Debug, Clone, Copy)]
+pub struct RomajiOutput {
+    chars: [char; MAX_ROMAJI_LENGTH],
+    length: usize,
+    position: usize,
+}
+
+impl RomajiOutput {
+    /// Empty output.
+    const fn new() -> RomajiOutput {
+        RomajiOutput {
+            chars: ['\0'; MAX_ROMAJI_LENGTH],
+            length: 0,
+            position: 0,
+        }
+    }
+
+    /// Appends a character.
+    fn push(&mut self, c: char) {
+        self.chars[self.length] = c;
+        self.length += 1;
+    }
+}
+
+impl Iterator for RomajiOutput {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars[self.position..self.length].first().copied()?;
+        self.position += 1;
+        Some(c)
+    }
+}
+
+/// Converts romaji into kana keystroke by keystroke.
+/// Letters which may start or continue a syllable, such as `ky`, are kept pending.
+/// - `n'` and `n` followed by anything but vowel or `y` become `ん`
+/// - `nn` becomes `ん`, and the second `n` starts the next syllable if vowel or `y` follows,
+///   as in Hepburn `onna`; otherwise it is dropped, as in `kannji`
+/// - `m` followed by `b` or `p` becomes `ん`, as in Hepburn `shimbun`
+/// - Doubled consonant and `tch` become `っ`
+/// - Other letters not forming syllable and non-letters are output as they are
+pub struct RomajiConverter {
+    pending: [u8; MAX_ROMAJI_LENGTH],
+    pending_length: usize,
+    script: KanaScript,
+}
+
+impl RomajiConverter {
+    /// Creates new converter.
+    pub const fn new(script: KanaScript) -> RomajiConverter {
+        RomajiConverter {
+            pending: [0; MAX_ROMAJI_LENGTH],
+            pending_length: 0,
+            script,
+        }
+    }
+
+    /// Returns the script to output.
+    pub fn script(&self) -> KanaScript {
+        self.script
+    }
+
+    /// Changes the script to output.
+    pub fn set_script(&mut self, script: KanaScript) {
+        self.script = script;
+    }
+
+    /// Returns pending romaji, to be displayed after the converted text.
+    pub fn pending(&self) -> &str {
+        from_utf8(&self.pending[..self.pending_length]).unwrap_or_default()
+    }
+
+    /// Whether any romaji is pending.
+    pub fn is_pending(&self) -> bool {
+        self.pending_length != 0
+    }
+
+    /// Removes the last pending letter.
+    /// Returns `false` if nothing is pending, so that the caller can delete converted text instead.
+    pub fn backspace(&mut self) -> bool {
+        if self.pending_length == 0 {
+            return false;
+        }
+        self.pending_length -= 1;
+        true
+    }
+
+    /// Inputs a character and returns converted characters.
+    /// Letters are matched case-insensitively, and output as typed if not converted.
+    pub fn push(&mut self, c: char) -> RomajiOutput {
+        let mut output = RomajiOutput::new();
+        if !c.is_ascii() {
+            self.flush(&mut output);
+            output.push(c);
+            return output;
+        }
+
+        self.pending[self.pending_length] = c as u8;
+        self.pending_length += 1;
+        while self.pending_length != 0 {
+            let romaji = &self.pending[..self.pending_length];
+            if romaji.eq_ignore_ascii_case(b"nn") {
+                break;
+            }
+            if let Some(kana) = lookup(romaji) {
+                self.push_kana(&mut output, kana);
+                self.pending_length = 0;
+                break;
+            }
+            if is_prefix(romaji) {
+                break;
+            }
+
+            let first = romaji[0].to_ascii_lowercase();
+            let second = romaji.get(1).map_or(0, u8::to_ascii_lowercase);
+            match (first, second) {
+                (b'n', b'n') => {
+                    let third = romaji.get(2).map_or(0, u8::to_ascii_lowercase);
+                    self.push_kana(&mut output, "ん");
+                    if !b"aiueoy".contains(&third) {
+                        self.pending.copy_within(1..self.pending_length, 0);
+                        self.pending_length -= 1;
+                    }
+                }
+                (b'n', b) if b != b'y' => self.push_kana(&mut output, "ん"),
+                (b'm', b'b' | b'p') => self.push_kana(&mut output, "ん"),
+                (b't', b'c') => self.push_kana(&mut output, "っ"),
+                (a, b) if a == b && a.is_ascii_lowercase() && !b"aiueon".contains(&a) => {
+                    self.push_kana(&mut output, "っ")
+                }
+                _ => output.push(romaji[0] as char),
+            }
+            self.pending.copy_within(1..self.pending_length, 0);
+            self.pending_length -= 1;
+        }
+        output
+    }
+
+    /// Ends the input and returns pending romaji, where `n` and `nn` become `ん`.
+    pub fn finish(&mut self) -> RomajiOutput {
+        let mut output = RomajiOutput::new();
+        self.flush(&mut output);
+        output
+    }
+
+    /// Converts string and writes into `output`, keeping the last incomplete romaji pending.
+    pub fn convert_to(&mut self, s: &str, output: &mut impl Write) -> FmtResult {
+        for c in s.chars() {
+            for converted in self.push(c) {
+                output.write_char(converted)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Outputs pending romaji.
+    fn flush(&mut self, output: &mut RomajiOutput) {
+        let pending = &self.pending[..self.pending_length];
+        if pending.eq_ignore_ascii_case(b"n") || pending.eq_ignore_ascii_case(b"nn") {
+            self.push_kana(output, "ん");
+        } else {
+            pending.iter().for_each(|&b| output.push(b as char));
+        }
+        self.pending_length = 0;
+    }
+
+    /// Outputs kana in the script.
+    fn push_kana(&self, output: &mut RomajiOutput, kana: &str) {
+        for c in kana.chars() {
+            output.push(match self.script {
+                KanaScript::Hiragana => c,
+                KanaScript::Katakana => hiragana_to_katakana(c).unwrap_or(c),
+            });
+        }
+    }
+}
+
+/// Returns kana for complete romaji.
+fn lookup(romaji: &[u8]) -> Option<&'static str> {
+    ROMAJI
+        .split_ascii_whitespace()
+        .zip(KANA.split_ascii_whitespace())
+        .find_map(|(r, k)| r.as_bytes().eq_ignore_ascii_case(romaji).then_some(k))
+}
+
+/// Whether romaji may be continued into a longer one.
+fn is_prefix(romaji: &[u8]) -> bool {
+    ROMAJI.split_ascii_whitespace().any(|r| {
+        r.len() > romaji.len() && r.as_bytes()[..romaji.len()].eq_ignore_ascii_case(romaji)
+    })
+}
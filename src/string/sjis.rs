@@ -0,0 +1,176 @@
+//! Shift_JIS codec.
+
+use crate::string::{
+    encode_replacing, transliterate::find_with_fallback, write_decoded, EncodedChar,
+    Jis2UnicodeTable, Unicode2JisTable, REPLACEMENT_CHAR,
+};
+
+use core::fmt::{Result as FmtResult, Write};
+
+/// Converts Shift_JIS double-byte sequence into JIS kuten code.
+/// Returns `None` if the bytes are not valid lead and trail bytes.
+pub fn sjis_to_kuten(lead: u8, trail: u8) -> Option<(u8, u8)> {
+    let row_pair = match lead {
+        0x81..=0x9F => lead - 0x81,
+        0xE0..=0xEF => lead - 0xC1,
+        _ => return None,
+    };
+    match trail {
+        0x40..=0x7E => Some((row_pair * 2 + 1, trail - 0x3F)),
+        0x80..=0x9E => Some((row_pair * 2 + 1, trail - 0x40)),
+        0x9F..=0xFC => Some((row_pair * 2 + 2, trail - 0x9E)),
+        _ => None,
+    }
+}
+
+/// Whether the byte can be a trail byte of double-byte sequence.
+const fn is_trail_byte(byte: u8) -> bool {
+    matches!(byte, 0x40..=0x7E | 0x80..=0xFC)
+}
+
+/// Converts JIS kuten code into Shift_JIS double-byte sequence.
+/// Both ku and ten must be in `1..=94`.
+pub fn kuten_to_sjis((ku, ten): (u8, u8)) -> [u8; 2] {
+    let lead = match ku {
+        1..=62 => ku.div_ceil(2) + 0x80,
+        _ => ku.div_ceil(2) + 0xC0,
+    };
+    let trail = match (ku % 2, ten) {
+        (1, 1..=63) => ten + 0x3F,
+        (1, _) => ten + 0x40,
+        _ => ten + 0x9E,
+    };
+    [lead, trail]
+}
+
+/// Decodes Shift_JIS byte stream incrementally.
+/// A lead byte at the end of input is kept until the next call.
+pub struct ShiftJisDecoder<'a> {
+    table: Jis2UnicodeTable<'a>,
+    pending_lead: Option<u8>,
+}
+
+impl<'a> ShiftJisDecoder<'a> {
+    /// Creates new decoder.
+    pub const fn new(table: Jis2UnicodeTable<'a>) -> ShiftJisDecoder<'a> {
+        ShiftJisDecoder {
+            table,
+            pending_lead: None,
+        }
+    }
+
+    /// Decodes a chunk of bytes.
+    /// Invalid sequences and unmapped characters are decoded as U+FFFD.
+    pub fn decode<'d>(&'d mut self, bytes: &'d [u8]) -> ShiftJisDecode<'d, 'a> {
+        ShiftJisDecode {
+            decoder: self,
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes and writes them into `output` as UTF-8.
+    /// If `output` fails, the rest of the chunk is discarded.
+    pub fn decode_to(&mut self, bytes: &[u8], output: &mut impl Write) -> FmtResult {
+        write_decoded(self.decode(bytes), output)
+    }
+
+    /// Ends the stream.
+    /// If a lead byte is left, U+FFFD will return.
+    pub fn finish(&mut self) -> Option<char> {
+        self.pending_lead.take().map(|_| REPLACEMENT_CHAR)
+    }
+
+    /// Whether a lead byte is waiting for its trail byte.
+    pub fn is_pending(&self) -> bool {
+        self.pending_lead.is_some()
+    }
+}
+
+/// Iterator of characters returned by `ShiftJisDecoder::decode`.
+pub struct ShiftJisDecode<'d, 'a> {
+    decoder: &'d mut ShiftJisDecoder<'a>,
+    bytes: &'d [u8],
+    position: usize,
+}
+
+impl<'d, 'a> Iterator for ShiftJisDecode<'d, 'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let &byte = self.bytes.get(self.position)?;
+        self.position += 1;
+
+        if let Some(lead) = self.decoder.pending_lead.take() {
+            return match sjis_to_kuten(lead, byte) {
+                Some(kuten) => Some(self.decoder.table.query(kuten).unwrap_or(REPLACEMENT_CHAR)),
+                // Lead bytes beyond JIS rows, such as user-defined area, take their trail byte.
+                None if lead >= 0xF0 && is_trail_byte(byte) => Some(REPLACEMENT_CHAR),
+                None => {
+                    // ASCII byte is not consumed as trail.
+                    if byte < 0x80 {
+                        self.position -= 1;
+                    }
+                    Some(REPLACEMENT_CHAR)
+                }
+            };
+        }
+
+        match byte {
+            0x00..=0x7F => Some(byte as char),
+            0xA1..=0xDF => char::from_u32(0xFF61 + (byte - 0xA1) as u32),
+            0x81..=0x9F | 0xE0..=0xFC => {
+                self.decoder.pending_lead = Some(byte);
+                self.next()
+            }
+            _ => Some(REPLACEMENT_CHAR),
+        }
+    }
+}
+
+/// Encodes string into Shift_JIS.
+/// Characters missing from the table are retried with substitutes by `fallback_char`,
+/// unless fallback is disabled.
+pub struct ShiftJisEncoder<'a> {
+    table: Unicode2JisTable<'a>,
+    fallback: bool,
+}
+
+impl<'a> ShiftJisEncoder<'a> {
+    /// Creates new encoder.
+    pub const fn new(table: Unicode2JisTable<'a>) -> ShiftJisEncoder<'a> {
+        ShiftJisEncoder {
+            table,
+            fallback: true,
+        }
+    }
+
+    /// Enables or disables fallback.
+    pub fn set_fallback(&mut self, enabled: bool) {
+        self.fallback = enabled;
+    }
+
+    /// Encodes a character.
+    /// Returns `None` if neither it nor its substitutes can be represented.
+    pub fn encode_char(&self, c: char) -> Option<EncodedChar> {
+        find_with_fallback(c, self.fallback, |s| self.encode_exact(s))
+    }
+
+    /// Encodes a character without substitution.
+    fn encode_exact(&self, c: char) -> Option<EncodedChar> {
+        match c as u32 {
+            0x00..=0x7F => Some(EncodedChar::new(&[c as u8])),
+            codepoint @ 0xFF61..=0xFF9F => {
+                Some(EncodedChar::new(&[(codepoint - 0xFF61) as u8 + 0xA1]))
+            }
+            _ => Some(EncodedChar::new(&kuten_to_sjis(self.table.query(c)?))),
+        }
+    }
+
+    /// Encodes a string and passes bytes to `write_bytes` for each character.
+    /// Unrepresentable characters are replaced with `?`.
+    /// Returns the number of replaced characters.
+    pub fn encode(&self, text: &str, write_bytes: impl FnMut(&[u8])) -> usize {
+        encode_replacing(text, |c| self.encode_char(c), write_bytes)
+    }
+}
@@ -0,0 +1,63 @@
+//! Character substitution for glyphs missing from fonts.
+
+use crate::string::kana::{ascii_to_fullwidth, halfwidth_to_fullwidth_katakana, translate};
+
+use core::iter::successors;
+
+/// How many times a substitute is replaced with its substitute.
+const FALLBACK_STEPS: usize = 3;
+
+/// Symbols and their substitutes, mostly the pairs mapped differently by JIS and vendors.
+const SYMBOLS_FROM: &str = concat!(
+    "\u{301C}\u{FF5E}\u{2016}\u{2225}\u{2212}\u{FF0D}\u{2014}\u{2015}\u{2013}",
+    "\u{A2}\u{FFE0}\u{A3}\u{FFE1}\u{AC}\u{FFE2}\u{A6}\u{203E}\u{A5}",
+    "\u{2022}\u{B7}\u{2219}\u{A0}①②③④⑤⑥⑦⑧⑨",
+);
+const SYMBOLS_TO: &str = concat!(
+    "\u{FF5E}\u{301C}\u{2225}\u{2016}\u{FF0D}\u{2212}\u{2015}\u{2014}\u{FF0D}",
+    "\u{FFE0}\u{A2}\u{FFE1}\u{A3}\u{FFE2}\u{AC}\u{FFE4}\u{FFE3}\u{FFE5}",
+    "\u{30FB}\u{30FB}\u{30FB} 123456789",
+);
+
+/// ASCII letters for Latin-1 Supplement and Latin Extended-A letters from U+00C0.
+/// Space means no substitute.
+const LATIN_BASES: &str = concat!(
+    "AAAAAA CEEEEIIII NOOOOO OUUUUY  aaaaaa ceeeeiiii nooooo ouuuuy y",
+    "AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIi  JjKk LlLlLlL",
+    "lLlNnNnNn   OoOoOo  RrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZz ",
+);
+
+/// Returns a substitute of the character, which is more likely to have a glyph.
+/// - Halfwidth katakana to fullwidth
+/// - Lone combining sound marks to spacing ones
+/// - ASCII to fullwidth forms, and controls to ideographic space
+/// - Symbols to similar ones, circled digits to digits
+/// - Latin letters with diacritics to ASCII letters
+///
+/// Substitutes may have substitutes again, such as `é` to `e` to `ｅ`.
+pub fn fallback_char(c: char) -> Option<char> {
+    match c {
+        '\u{FF61}'..='\u{FF9F}' => halfwidth_to_fullwidth_katakana(c),
+        '\u{3099}' => Some('\u{309B}'),
+        '\u{309A}' => Some('\u{309C}'),
+        '\u{00}'..=' ' | '\u{7F}' => Some('\u{3000}'),
+        '!'..='~' => ascii_to_fullwidth(c),
+        '\u{C0}'..='\u{17F}' => {
+            let base = LATIN_BASES.as_bytes()[c as usize - 0xC0];
+            (base != b' ').then_some(base as char)
+        }
+        _ => translate(c, SYMBOLS_FROM, SYMBOLS_TO),
+    }
+}
+
+/// Returns the first result of `f` for the character, or for its substitutes if `fallback` is enabled.
+pub(crate) fn find_with_fallback<T>(
+    c: char,
+    fallback: bool,
+    f: impl FnMut(char) -> Option<T>,
+) -> Option<T> {
+    let steps = if fallback { FALLBACK_STEPS } else { 0 };
+    successors(Some(c), |&s| fallback_char(s))
+        .take(steps + 1)
+        .find_map(f)
+}
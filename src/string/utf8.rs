@@ -0,0 +1,180 @@
+//! Incremental UTF-8 decoder.
+
+use crate::string::{write_decoded, REPLACEMENT_CHAR};
+
+use core::fmt::{Result as FmtResult, Write};
+
+/// Invalid byte sequence found by `Utf8Decoder`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utf8DecodeError {
+    /// Offset of the first invalid byte from the beginning of the stream.
+    pub position: usize,
+
+    /// Number of bytes replaced by U+FFFD.
+    pub length: usize,
+}
+
+/// Sequence waiting for its continuation bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pending {
+    /// Codepoint bits decoded so far.
+    code: u32,
+
+    /// Bytes consumed so far, including the lead byte.
+    seen: u8,
+
+    /// Bytes of the whole sequence.
+    needed: u8,
+
+    /// Range of the next continuation byte, narrowed to reject overlong forms and surrogates.
+    lower: u8,
+    upper: u8,
+}
+
+/// Decodes UTF-8 byte stream incrementally.
+/// An incomplete sequence at the end of input is kept until the next call,
+/// so characters split across packets are decoded correctly.
+pub struct Utf8Decoder {
+    pending: Option<Pending>,
+    pending_position: usize,
+    position: usize,
+}
+
+impl Utf8Decoder {
+    /// Creates new decoder.
+    pub const fn new() -> Utf8Decoder {
+        Utf8Decoder {
+            pending: None,
+            pending_position: 0,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes.
+    /// Invalid sequences are decoded as U+FFFD, one for each maximal invalid subpart.
+    pub fn decode<'d>(&'d mut self, bytes: &'d [u8]) -> Utf8Decode<'d> {
+        Utf8Decode {
+            decoder: self,
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Decodes a chunk of bytes and writes them into `output`.
+    /// If `output` fails, the rest of the chunk is discarded.
+    pub fn decode_to(&mut self, bytes: &[u8], output: &mut impl Write) -> FmtResult {
+        write_decoded(self.decode(bytes), output)
+    }
+
+    /// Ends the stream and resets the position.
+    /// If an incomplete sequence is left, U+FFFD will return.
+    pub fn finish(&mut self) -> Option<char> {
+        self.finish_checked().map(|_| REPLACEMENT_CHAR)
+    }
+
+    /// Ends the stream and resets the position.
+    /// If an incomplete sequence is left, it is reported instead of U+FFFD.
+    pub fn finish_checked(&mut self) -> Option<Utf8DecodeError> {
+        self.position = 0;
+        let pending = self.pending.take()?;
+        Some(Utf8DecodeError {
+            position: self.pending_position,
+            length: pending.seen as usize,
+        })
+    }
+
+    /// Whether an incomplete sequence is waiting for the rest.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Returns the number of bytes consumed since the beginning of the stream.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl Default for Utf8Decoder {
+    fn default() -> Self {
+        Utf8Decoder::new()
+    }
+}
+
+/// Iterator of characters returned by `Utf8Decoder::decode`.
+pub struct Utf8Decode<'d> {
+    decoder: &'d mut Utf8Decoder,
+    bytes: &'d [u8],
+    position: usize,
+}
+
+impl<'d> Utf8Decode<'d> {
+    /// Decodes next character, reporting invalid sequence instead of U+FFFD.
+    pub fn next_checked(&mut self) -> Option<Result<char, Utf8DecodeError>> {
+        loop {
+            let &byte = self.bytes.get(self.position)?;
+
+            let Some(mut pending) = self.decoder.pending.take() else {
+                self.consume();
+                let (code, needed, lower, upper) = match byte {
+                    0x00..=0x7F => return Some(Ok(byte as char)),
+                    0xC2..=0xDF => (byte & 0x1F, 2, 0x80, 0xBF),
+                    0xE0 => (byte & 0x0F, 3, 0xA0, 0xBF),
+                    0xED => (byte & 0x0F, 3, 0x80, 0x9F),
+                    0xE1..=0xEF => (byte & 0x0F, 3, 0x80, 0xBF),
+                    0xF0 => (byte & 0x07, 4, 0x90, 0xBF),
+                    0xF4 => (byte & 0x07, 4, 0x80, 0x8F),
+                    0xF1..=0xF3 => (byte & 0x07, 4, 0x80, 0xBF),
+                    _ => {
+                        return Some(Err(Utf8DecodeError {
+                            position: self.decoder.position - 1,
+                            length: 1,
+                        }))
+                    }
+                };
+                self.decoder.pending_position = self.decoder.position - 1;
+                self.decoder.pending = Some(Pending {
+                    code: code as u32,
+                    seen: 1,
+                    needed,
+                    lower,
+                    upper,
+                });
+                continue;
+            };
+
+            // The byte is not consumed, and may start the next sequence.
+            if !(pending.lower..=pending.upper).contains(&byte) {
+                return Some(Err(Utf8DecodeError {
+                    position: self.decoder.pending_position,
+                    length: pending.seen as usize,
+                }));
+            }
+
+            self.consume();
+            pending.code = (pending.code << 6) | (byte & 0x3F) as u32;
+            pending.seen += 1;
+            pending.lower = 0x80;
+            pending.upper = 0xBF;
+            if pending.seen < pending.needed {
+                self.decoder.pending = Some(pending);
+                continue;
+            }
+            return Some(Ok(char::from_u32(pending.code).unwrap_or(REPLACEMENT_CHAR)));
+        }
+    }
+
+    /// Advances both this chunk and the stream by a byte.
+    fn consume(&mut self) {
+        self.position += 1;
+        self.decoder.position += 1;
+    }
+}
+
+impl<'d> Iterator for Utf8Decode<'d> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.next_checked()
+            .map(|decoded| decoded.unwrap_or(REPLACEMENT_CHAR))
+    }
+}
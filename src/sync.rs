@@ -0,0 +1,39 @@
+//! Synchronization primitives for sharing state with interrupt handlers and the other core.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+/// Mutable value guarded by critical section.
+/// Unlike `RefCell`, it can be placed in `static` and shared between cores and interrupt handlers.
+pub struct SharedCell<T> {
+    inner: Mutex<RefCell<T>>,
+}
+
+impl<T> SharedCell<T> {
+    /// Wraps the value.
+    pub const fn new(value: T) -> SharedCell<T> {
+        SharedCell {
+            inner: Mutex::new(RefCell::new(value)),
+        }
+    }
+
+    /// Runs `f` with exclusive access to the value inside critical section.
+    /// Keep `f` short; interrupts are masked while it runs.
+    ///
+    /// # Panics
+    /// Panics if called again from inside `f`.
+    pub fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        critical_section::with(|cs| f(&mut self.inner.borrow_ref_mut(cs)))
+    }
+
+    /// Returns mutable reference without critical section.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.inner.get_mut().get_mut()
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> T {
+        self.inner.into_inner().into_inner()
+    }
+}
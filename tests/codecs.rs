@@ -0,0 +1,151 @@
+//! Encodes and decodes legacy Japanese encodings on the host.
+//!
+//! ```text
+//! cargo test --target <host triple> --test codecs
+//! ```
+
+use picolony::string::{
+    eucjp::{EucJpDecoder, EucJpEncoder},
+    iso2022jp::{Iso2022JpDecoder, Iso2022JpEncoder},
+    sjis::{ShiftJisDecoder, ShiftJisEncoder},
+    Jis2UnicodeTable, Unicode2JisTable, JIS2UNI_DATA, UNI2JIS_DATA,
+};
+
+/// Chunk sizes to split encoded bytes into, so that sequences are split at every position.
+const CHUNK_SIZES: [usize; 4] = [1, 2, 3, 7];
+
+fn jis2uni() -> Jis2UnicodeTable<'static> {
+    Jis2UnicodeTable::new(JIS2UNI_DATA).unwrap()
+}
+
+fn uni2jis() -> Unicode2JisTable<'static> {
+    Unicode2JisTable::new(UNI2JIS_DATA).unwrap()
+}
+
+/// Returns all characters in the kuten table, with ASCII and newlines between rows.
+fn table_text() -> String {
+    let table = jis2uni();
+    let mut text = String::new();
+    for ku in 1..=94 {
+        text.push_str("row\n");
+        text.extend((1..=94).filter_map(|ten| table.query((ku, ten))));
+    }
+    text
+}
+
+/// Returns halfwidth katakana.
+fn halfwidth_katakana() -> String {
+    ('\u{FF61}'..='\u{FF9F}').collect()
+}
+
+#[test]
+fn shift_jis_round_trips_table() {
+    let text = table_text() + &halfwidth_katakana();
+    let mut bytes = Vec::new();
+    let encoder = ShiftJisEncoder::new(uni2jis());
+    assert_eq!(encoder.encode(&text, |b| bytes.extend_from_slice(b)), 0);
+
+    for chunk_size in CHUNK_SIZES {
+        let mut decoder = ShiftJisDecoder::new(jis2uni());
+        let mut decoded = String::new();
+        for chunk in bytes.chunks(chunk_size) {
+            decoded.extend(decoder.decode(chunk));
+        }
+        assert_eq!(decoder.finish(), None);
+        assert_eq!(decoded, text, "chunk size {chunk_size}");
+    }
+}
+
+#[test]
+fn euc_jp_round_trips_table() {
+    let text = table_text() + &halfwidth_katakana();
+    let mut bytes = Vec::new();
+    let encoder = EucJpEncoder::new(uni2jis());
+    assert_eq!(encoder.encode(&text, |b| bytes.extend_from_slice(b)), 0);
+
+    for chunk_size in CHUNK_SIZES {
+        let mut decoder = EucJpDecoder::new(jis2uni());
+        let mut decoded = String::new();
+        for chunk in bytes.chunks(chunk_size) {
+            decoded.extend(decoder.decode(chunk));
+        }
+        assert_eq!(decoder.finish(), None);
+        assert_eq!(decoded, text, "chunk size {chunk_size}");
+    }
+}
+
+#[test]
+fn iso_2022_jp_round_trips_table() {
+    let text = table_text();
+    let mut bytes = Vec::new();
+    let mut encoder = Iso2022JpEncoder::new(uni2jis());
+    assert_eq!(encoder.encode(&text, |b| bytes.extend_from_slice(b)), 0);
+
+    for chunk_size in CHUNK_SIZES {
+        let mut decoder = Iso2022JpDecoder::new(jis2uni());
+        let mut decoded = String::new();
+        for chunk in bytes.chunks(chunk_size) {
+            decoded.extend(decoder.decode(chunk));
+        }
+        assert_eq!(decoder.finish(), None);
+        assert_eq!(decoded, text, "chunk size {chunk_size}");
+    }
+}
+
+#[test]
+fn encoders_substitute_missing_characters() {
+    // WAVE DASH, MINUS SIGN and YEN SIGN are mapped to fullwidth forms in the table.
+    let text = "\u{301C}\u{2212}\u{A5}";
+
+    let mut bytes = Vec::new();
+    assert_eq!(
+        ShiftJisEncoder::new(uni2jis()).encode(text, |b| bytes.extend_from_slice(b)),
+        0
+    );
+    assert_eq!(bytes, b"\x81\x60\x81\x7C\x81\x8F");
+
+    let mut bytes = Vec::new();
+    assert_eq!(
+        EucJpEncoder::new(uni2jis()).encode(text, |b| bytes.extend_from_slice(b)),
+        0
+    );
+    assert_eq!(bytes, b"\xA1\xC1\xA1\xDD\xA1\xEF");
+
+    let mut bytes = Vec::new();
+    let mut encoder = Iso2022JpEncoder::new(uni2jis());
+    assert_eq!(
+        encoder.encode("\u{FF71}\u{301C}", |b| bytes.extend_from_slice(b)),
+        0
+    );
+    assert_eq!(bytes, b"\x1B$B%\x22!A\x1B(B");
+}
+
+#[test]
+fn encoders_replace_without_fallback() {
+    let mut bytes = Vec::new();
+    let mut encoder = ShiftJisEncoder::new(uni2jis());
+    encoder.set_fallback(false);
+    assert_eq!(
+        encoder.encode("\u{2212}", |b| bytes.extend_from_slice(b)),
+        1
+    );
+    assert_eq!(bytes, b"?");
+
+    let mut bytes = Vec::new();
+    let mut encoder = EucJpEncoder::new(uni2jis());
+    encoder.set_fallback(false);
+    assert_eq!(
+        encoder.encode("\u{301C}", |b| bytes.extend_from_slice(b)),
+        1
+    );
+    assert_eq!(bytes, b"?");
+
+    let mut bytes = Vec::new();
+    let mut encoder = Iso2022JpEncoder::new(uni2jis());
+    encoder.set_fallback(false);
+    assert_eq!(
+        encoder.encode("\u{FF71}\u{301C}", |b| bytes.extend_from_slice(b)),
+        2
+    );
+    assert_eq!(bytes, b"??");
+}
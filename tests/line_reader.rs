@@ -0,0 +1,32 @@
+//! Reads lines with invalid UTF-8 on the host.
+//!
+//! ```text
+//! cargo test --target <host triple> --test line_reader
+//! ```
+
+use picolony::{io::LineReader, string::utf8::Utf8DecodeError};
+
+#[test]
+fn keeps_received_bytes() {
+    let mut reader = LineReader::<8>::new();
+    assert!(reader.poll_read(b"a\xFFb\n"));
+    assert_eq!(reader.ready_bytes(), Some(&b"a\xFFb"[..]));
+    assert_eq!(reader.ready_str(), Some("a\u{FFFD}b"));
+    assert_eq!(reader.ready_errors(), 1);
+    assert_eq!(
+        reader.ready_first_error(),
+        Some(Utf8DecodeError {
+            position: 1,
+            length: 1
+        })
+    );
+}
+
+#[test]
+fn cuts_off_text_not_fitting() {
+    let mut reader = LineReader::<4>::new();
+    assert!(reader.poll_read(b"ab\xFFc\n"));
+    assert_eq!(reader.ready_bytes(), Some(&b"ab\xFFc"[..]));
+    assert_eq!(reader.ready_str(), Some("ab"));
+    assert_eq!(reader.ready_errors(), 1);
+}
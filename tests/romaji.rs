@@ -0,0 +1,60 @@
+//! Converts romaji into kana on the host.
+//!
+//! ```text
+//! cargo test --target <host triple> --test romaji
+//! ```
+
+use picolony::string::romaji::{KanaScript, RomajiConverter};
+
+/// Converts whole text, including the pending romaji at the end.
+fn convert(text: &str, script: KanaScript) -> String {
+    let mut converter = RomajiConverter::new(script);
+    let mut output = String::new();
+    converter.convert_to(text, &mut output).unwrap();
+    output.extend(converter.finish());
+    output
+}
+
+#[test]
+fn converts_double_n_before_syllable() {
+    assert_eq!(convert("onna", KanaScript::Hiragana), "おんな");
+    assert_eq!(convert("konnichiha", KanaScript::Hiragana), "こんにちは");
+    assert_eq!(convert("konnyaku", KanaScript::Hiragana), "こんにゃく");
+    assert_eq!(convert("Onna", KanaScript::Katakana), "オンナ");
+}
+
+#[test]
+fn converts_double_n_as_single() {
+    assert_eq!(convert("kannji", KanaScript::Hiragana), "かんじ");
+    assert_eq!(convert("kanji", KanaScript::Hiragana), "かんじ");
+    assert_eq!(convert("kon'ya", KanaScript::Hiragana), "こんや");
+    assert_eq!(convert("honn", KanaScript::Hiragana), "ほん");
+    assert_eq!(convert("honn desu", KanaScript::Hiragana), "ほん です");
+}
+
+#[test]
+fn keeps_double_n_pending() {
+    let mut converter = RomajiConverter::new(KanaScript::Hiragana);
+    assert_eq!(converter.push('n').count(), 0);
+    assert_eq!(converter.push('n').count(), 0);
+    assert_eq!(converter.pending(), "nn");
+    assert_eq!(converter.push('a').collect::<String>(), "んな");
+    assert!(!converter.is_pending());
+}
+
+#[test]
+fn converts_n_before_labial() {
+    assert_eq!(convert("shimbun", KanaScript::Hiragana), "しんぶん");
+    assert_eq!(convert("sempai", KanaScript::Hiragana), "せんぱい");
+    assert_eq!(convert("shinbun", KanaScript::Hiragana), "しんぶん");
+    assert_eq!(convert("mama", KanaScript::Hiragana), "まま");
+}
+
+#[test]
+fn converts_sokuon() {
+    assert_eq!(convert("gakkou", KanaScript::Hiragana), "がっこう");
+    assert_eq!(convert("kitte", KanaScript::Hiragana), "きって");
+    assert_eq!(convert("matcha", KanaScript::Hiragana), "まっちゃ");
+    assert_eq!(convert("mattya", KanaScript::Hiragana), "まっちゃ");
+    assert_eq!(convert("zasshi", KanaScript::Katakana), "ザッシ");
+}
@@ -0,0 +1,57 @@
+//! Draws through a font shared in `SharedCell`, with std critical section on the host.
+//!
+//! ```text
+//! cargo test --target <host triple> --test shared_font
+//! ```
+
+use core::convert::Infallible;
+
+use embedded_graphics_core::{pixelcolor::BinaryColor, prelude::*};
+use picolony::{
+    graphics::{
+        drawables::{JisText, JisTextStyle},
+        font::{JisFont, JisFont8x12},
+    },
+    sync::SharedCell,
+};
+
+static BITMAP: [u8; 94 * 94 * 12] = [0; 94 * 94 * 12];
+static FONT: SharedCell<JisFont<'static, JisFont8x12, 4>> =
+    SharedCell::new(JisFont::new_uninit(&BITMAP));
+
+/// Counts pixels, and locks the font as an interrupt handler would while drawing.
+struct LockingTarget(usize);
+
+impl OriginDimensions for LockingTarget {
+    fn size(&self) -> Size {
+        Size::new(128, 96)
+    }
+}
+
+impl DrawTarget for LockingTarget {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        // Panics if the font is still borrowed for drawing.
+        FONT.with(|font| font.cache_stats());
+        self.0 += pixels.into_iter().count();
+        Ok(())
+    }
+}
+
+#[test]
+fn draws_without_holding_font() {
+    FONT.with(|font| font.init()).unwrap();
+    let style = JisTextStyle::new(&FONT, BinaryColor::On).with_background(BinaryColor::Off);
+
+    let mut target = LockingTarget(0);
+    let output = JisText::new("あいう\nえ", Point::zero(), &style)
+        .draw(&mut target)
+        .unwrap();
+    assert_eq!((output.width, output.height, output.missing), (24, 24, 0));
+    assert_eq!(target.0, 4 * 8 * 12);
+}
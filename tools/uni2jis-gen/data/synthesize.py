@@ -0,0 +1,79 @@
+"""Writes mapping text files from CPython codecs, for environments without the original files.
+
+    python3 synthesize.py
+
+CP932.TXT is decoded from `cp932` in the layout of the Unicode consortium file.
+JISX0213-2004.TXT is decoded from `shift_jis_2004`, which covers JIS X 0213:2004 only.
+`euc_jis_2004` is not used, as it falls back to JIS X 0212 in unassigned plane 2 rows.
+The codec decodes 1-1-32 and 1-2-18 into ASCII, which the standard maps to fullwidth forms.
+"""
+
+import sys
+
+# Plane 2 rows of each Shift_JIS-2004 lead byte from 0xF0.
+PLANE2_ROW_PAIRS = [(1, 8), (3, 4), (5, 12), (13, 14), (15, 78)] + [
+    (79 + 2 * i, 80 + 2 * i) for i in range(8)
+]
+
+
+def sjis_to_menkuten(lead, trail):
+    if 0x40 <= trail <= 0x9E and trail != 0x7F:
+        second, ten = False, trail - (0x3F if trail < 0x7F else 0x40)
+    elif 0x9F <= trail <= 0xFC:
+        second, ten = True, trail - 0x9E
+    else:
+        return None
+    if 0x81 <= lead <= 0x9F or 0xE0 <= lead <= 0xEF:
+        pair = lead - (0x81 if lead <= 0x9F else 0xC1)
+        return 1, pair * 2 + 1 + second, ten
+    if 0xF0 <= lead <= 0xFC:
+        return 2, PLANE2_ROW_PAIRS[lead - 0xF0][second], ten
+    return None
+
+
+def write_cp932(path):
+    with open(path, "w", newline="\n") as f:
+        f.write(f"#\tCP932 synthesized from CPython {sys.version.split()[0]} cp932\n")
+        codes = [(b,) for b in range(0x100)] + [
+            (lead, trail)
+            for lead in list(range(0x81, 0xA0)) + list(range(0xE0, 0xFD))
+            for trail in range(0x40, 0xFD)
+        ]
+        for code in codes:
+            hex_code = "0x" + "".join(f"{b:02X}" for b in code)
+            try:
+                decoded = bytes(code).decode("cp932")
+            except UnicodeDecodeError:
+                if len(code) == 1:
+                    f.write(f"{hex_code}\t#UNDEFINED\n")
+                continue
+            # Lead bytes decode into nothing alone.
+            if len(decoded) == 1:
+                f.write(f"{hex_code}\t0x{ord(decoded):04X}\t#\n")
+
+
+def write_jisx0213(path):
+    lines = []
+    for lead in range(0x81, 0xFD):
+        for trail in range(0x40, 0xFD):
+            menkuten = sjis_to_menkuten(lead, trail)
+            if menkuten is None:
+                continue
+            try:
+                decoded = bytes([lead, trail]).decode("shift_jis_2004")
+            except UnicodeDecodeError:
+                continue
+            if decoded in ("\\", "~"):
+                decoded = chr(ord(decoded) + 0xFEE0)
+            men, ku, ten = menkuten
+            unicode = "+".join(f"{ord(c):04X}" for c in decoded)
+            lines.append((menkuten, f"{men + 2}-{ku + 0x20:02X}{ten + 0x20:02X}\tU+{unicode}"))
+    with open(path, "w", newline="\n") as f:
+        f.write(f"## JIS X 0213:2004 synthesized from CPython {sys.version.split()[0]} shift_jis_2004\n")
+        for _, line in sorted(lines):
+            f.write(line + "\n")
+
+
+if __name__ == "__main__":
+    write_cp932("CP932.TXT")
+    write_jisx0213("JISX0213-2004.TXT")
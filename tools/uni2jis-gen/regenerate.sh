@@ -0,0 +1,11 @@
+#!/bin/sh
+# Regenerates table assets from data/. With --check, fails if the assets differ instead.
+set -eu
+cd "$(dirname "$0")"
+
+cargo run --quiet -- "$@" --ascii \
+    -o ../../assets/uni2jis.bin --jis2uni ../../assets/jis2uni.bin \
+    sjis:data/CP932.TXT
+cargo run --quiet -- "$@" --ascii \
+    -o ../../assets/uni2jis0213.bin \
+    jisx0213:data/JISX0213-2004.TXT
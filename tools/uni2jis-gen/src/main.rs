@@ -0,0 +1,242 @@
+//! Generates `uni2jis.bin` and `jis2uni.bin` from mapping text files.
+//!
+//! ```text
+//! uni2jis-gen [OPTIONS] <FORMAT:PATH[:ROWS]>...
+//!
+//! FORMAT is one of `jis` (JIS0208.TXT), `sjis` (CP932.TXT) or `jisx0213`.
+//! ROWS limits the rows taken from the file, such as `13,89-92`.
+//! Inputs are merged in order.
+//!
+//! Options:
+//!   -o, --output PATH        Writes Unicode to kuten table.
+//!   --jis2uni PATH           Writes kuten to Unicode table.
+//!   --table-version N        1, 2 or 3. Defaults to 1, or 2 if non-BMP characters exist.
+//!   --chain-length N         Power of two. Defaults to 32.
+//!   --duplicates first|last  Which kuten code a duplicated codepoint takes in both tables.
+//!                            Defaults to first, which prefers JIS rows to vendor rows.
+//!   --ascii                  Maps ASCII to fullwidth glyphs.
+//!   --verify PATH            Compares Unicode to kuten table with PATH.
+//!   --verify-jis2uni PATH    Compares kuten to Unicode table with PATH.
+//!   --check                  Fails if output files differ from generated tables, instead of
+//!                            writing them.
+//! ```
+//!
+//! The shipped assets are generated from `data/` by `regenerate.sh`, and `regenerate.sh --check`
+//! confirms they are up to date. It runs, in this directory:
+//!
+//! ```text
+//! cargo run -- --ascii -o ../../assets/uni2jis.bin --jis2uni ../../assets/jis2uni.bin \
+//!     sjis:data/CP932.TXT
+//! cargo run -- --ascii -o ../../assets/uni2jis0213.bin jisx0213:data/JISX0213-2004.TXT
+//! ```
+//!
+//! `.cargo/config.toml` here builds for the host, overriding the firmware target of the
+//! repository root. Elsewhere, such as with `--manifest-path`, pass `--target <host triple>`.
+
+mod mapping;
+mod table;
+
+use crate::{
+    mapping::Input,
+    table::{
+        add_ascii, build_kuten2uni, build_uni2kuten, collect_codepoints, read_uni2kuten, Duplicates,
+    },
+};
+
+use std::{
+    env::args,
+    fs::{read, write},
+    process::ExitCode,
+};
+
+/// Command line options.
+struct Options {
+    inputs: Vec<Input>,
+    output: Option<String>,
+    jis2uni: Option<String>,
+    table_version: Option<u16>,
+    chain_length: u16,
+    duplicates: Duplicates,
+    ascii: bool,
+    verify: Option<String>,
+    verify_jis2uni: Option<String>,
+    check: bool,
+}
+
+impl Options {
+    fn parse(mut arguments: impl Iterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options {
+            inputs: vec![],
+            output: None,
+            jis2uni: None,
+            table_version: None,
+            chain_length: 32,
+            duplicates: Duplicates::First,
+            ascii: false,
+            verify: None,
+            verify_jis2uni: None,
+            check: false,
+        };
+
+        while let Some(argument) = arguments.next() {
+            let mut value = || {
+                arguments
+                    .next()
+                    .ok_or_else(|| format!("Missing value for {argument}"))
+            };
+            match argument.as_str() {
+                "-o" | "--output" => options.output = Some(value()?),
+                "--jis2uni" => options.jis2uni = Some(value()?),
+                "--table-version" => {
+                    let version = value()?;
+                    let version = version
+                        .parse()
+                        .map_err(|_| format!("Invalid version: {version}"))?;
+                    options.table_version = Some(version);
+                }
+                "--chain-length" => {
+                    let length = value()?;
+                    options.chain_length = length
+                        .parse()
+                        .map_err(|_| format!("Invalid chain length: {length}"))?;
+                }
+                "--duplicates" => {
+                    options.duplicates = match value()?.as_str() {
+                        "first" => Duplicates::First,
+                        "last" => Duplicates::Last,
+                        other => return Err(format!("Invalid duplicates policy: {other}")),
+                    };
+                }
+                "--ascii" => options.ascii = true,
+                "--verify" => options.verify = Some(value()?),
+                "--verify-jis2uni" => options.verify_jis2uni = Some(value()?),
+                "--check" => options.check = true,
+                _ if argument.starts_with('-') => {
+                    return Err(format!("Unknown option: {argument}"))
+                }
+                _ => options.inputs.push(Input::parse(&argument)?),
+            }
+        }
+
+        if options.inputs.is_empty() {
+            return Err("No input files".into());
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Generates tables. Returns whether verification passed.
+fn run() -> Result<bool, String> {
+    let options = Options::parse(args().skip(1))?;
+
+    let mut mappings = vec![];
+    for input in &options.inputs {
+        let input_mappings = input.read()?;
+        eprintln!(
+            "{}: {} mappings",
+            input.path.display(),
+            input_mappings.len()
+        );
+        mappings.extend(input_mappings);
+    }
+
+    let mut codepoints = collect_codepoints(&mappings, options.duplicates);
+    if options.ascii {
+        add_ascii(&mut codepoints);
+    }
+    let has_astral = codepoints.range(0x10000..).next().is_some();
+    let version = options
+        .table_version
+        .unwrap_or(if has_astral { 2 } else { 1 });
+    let uni2kuten = build_uni2kuten(&codepoints, version, options.chain_length)?;
+    eprintln!(
+        "Unicode to kuten: version {version}, {} codepoints, {} bytes",
+        codepoints.len(),
+        uni2kuten.len()
+    );
+
+    let mut verified = true;
+    if let Some(path) = &options.verify {
+        let existing = read(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        let existing = read_uni2kuten(&existing)?;
+        let differences = codepoints
+            .iter()
+            .filter(|(c, k)| existing.get(c) != Some(k))
+            .map(|(&c, &k)| (c, Some(k), existing.get(&c).copied()))
+            .chain(
+                existing
+                    .iter()
+                    .filter(|(c, _)| !codepoints.contains_key(c))
+                    .map(|(&c, &k)| (c, None, Some(k))),
+            );
+        let mut count = 0;
+        for (c, generated, existing) in differences {
+            println!("U+{c:04X}: generated {generated:?}, {path} {existing:?}");
+            count += 1;
+        }
+        eprintln!("{path}: {count} differences");
+        verified &= count == 0;
+    }
+
+    let kuten2uni = match options.jis2uni.is_some() || options.verify_jis2uni.is_some() {
+        true => Some(build_kuten2uni(&mappings, &codepoints)?),
+        false => None,
+    };
+    if let (Some(path), Some(kuten2uni)) = (&options.verify_jis2uni, &kuten2uni) {
+        let existing = read(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+        if existing.len() != kuten2uni.len() {
+            return Err(format!("{path}: size differs"));
+        }
+        let mut count = 0;
+        for (index, (generated, existing)) in
+            kuten2uni.chunks(3).zip(existing.chunks(3)).enumerate()
+        {
+            if generated != existing {
+                let codepoint = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], 0]);
+                println!(
+                    "{}-{}: generated U+{:04X}, {path} U+{:04X}",
+                    index / 94 + 1,
+                    index % 94 + 1,
+                    codepoint(generated),
+                    codepoint(existing)
+                );
+                count += 1;
+            }
+        }
+        eprintln!("{path}: {count} differences");
+        verified &= count == 0;
+    }
+
+    let outputs = [
+        (&options.output, Some(&uni2kuten)),
+        (&options.jis2uni, kuten2uni.as_ref()),
+    ];
+    for (path, bytes) in outputs {
+        let (Some(path), Some(bytes)) = (path, bytes) else {
+            continue;
+        };
+        if options.check {
+            let existing = read(path).map_err(|e| format!("Failed to read {path}: {e}"))?;
+            let up_to_date = existing == *bytes;
+            eprintln!(
+                "{path}: {}",
+                if up_to_date { "up to date" } else { "differs" }
+            );
+            verified &= up_to_date;
+        } else {
+            write(path, bytes).map_err(|e| format!("Failed to write {path}: {e}"))?;
+        }
+    }
+    Ok(verified)
+}
@@ -0,0 +1,196 @@
+//! Reads mapping text files.
+
+use std::{fs::read_to_string, ops::RangeInclusive, path::PathBuf};
+
+/// Width of kuten pages.
+pub const KUTEN_WIDTH: u8 = 94;
+
+/// A codepoint and its kuten code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mapping {
+    pub codepoint: u32,
+    pub kuten: (u8, u8),
+}
+
+/// Layout of mapping text file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// `[0xSJIS] 0xJIS 0xUNICODE`, such as JIS0208.TXT of Unicode consortium.
+    Jis,
+
+    /// `0xSJIS 0xUNICODE`, such as CP932.TXT. Single-byte codes are ignored.
+    ShiftJis,
+
+    /// `3-JIS U+UNICODE` or `4-JIS U+UNICODE` for plane 1 and 2 of JIS X 0213.
+    /// Combining sequences are ignored; they are built in `Charset94::SEQUENCES`.
+    Jisx0213,
+}
+
+/// Input file with format and rows to take.
+#[derive(Debug, Clone)]
+pub struct Input {
+    pub format: InputFormat,
+    pub path: PathBuf,
+    pub rows: Option<Vec<RangeInclusive<u8>>>,
+}
+
+impl Input {
+    /// Parses `FORMAT:PATH[:ROWS]` argument, where ROWS is like `13,89-92`.
+    pub fn parse(argument: &str) -> Result<Input, String> {
+        let (format, rest) = argument
+            .split_once(':')
+            .ok_or_else(|| format!("Input must be FORMAT:PATH[:ROWS]: {argument}"))?;
+        let format = match format {
+            "jis" => InputFormat::Jis,
+            "sjis" => InputFormat::ShiftJis,
+            "jisx0213" => InputFormat::Jisx0213,
+            _ => return Err(format!("Unknown input format: {format}")),
+        };
+
+        let (path, rows) = match rest.rsplit_once(':') {
+            Some((path, rows)) if rows.chars().all(|c| c.is_ascii_digit() || ",-".contains(c)) => {
+                (path, Some(parse_rows(rows)?))
+            }
+            _ => (rest, None),
+        };
+        Ok(Input {
+            format,
+            path: PathBuf::from(path),
+            rows,
+        })
+    }
+
+    /// Reads mappings in file order.
+    pub fn read(&self) -> Result<Vec<Mapping>, String> {
+        let text = read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {e}", self.path.display()))?;
+
+        let mut mappings = vec![];
+        for (line_index, line) in text.lines().enumerate() {
+            let content = line.split('#').next().unwrap_or_default();
+            let columns: Vec<_> = content.split_whitespace().collect();
+            let mapping = match self.format {
+                InputFormat::Jis => parse_jis(&columns),
+                InputFormat::ShiftJis => parse_sjis(&columns),
+                InputFormat::Jisx0213 => parse_jisx0213(&columns),
+            }
+            .map_err(|e| format!("{}:{}: {e}", self.path.display(), line_index + 1))?;
+
+            let Some(mapping) = mapping else {
+                continue;
+            };
+            let row_taken = match &self.rows {
+                Some(rows) => rows.iter().any(|r| r.contains(&mapping.kuten.0)),
+                None => true,
+            };
+            if row_taken {
+                mappings.push(mapping);
+            }
+        }
+        Ok(mappings)
+    }
+}
+
+/// Parses row list like `13,89-92`.
+fn parse_rows(rows: &str) -> Result<Vec<RangeInclusive<u8>>, String> {
+    let parse_row = |row: &str| row.parse::<u8>().map_err(|_| format!("Invalid row: {row}"));
+    rows.split(',')
+        .map(|range| match range.split_once('-') {
+            Some((start, end)) => Ok(parse_row(start)?..=parse_row(end)?),
+            None => {
+                let row = parse_row(range)?;
+                Ok(row..=row)
+            }
+        })
+        .collect()
+}
+
+fn parse_hex(column: &str, prefix: &str) -> Result<u32, String> {
+    let digits = column
+        .strip_prefix(prefix)
+        .ok_or_else(|| format!("Expected {prefix} prefix: {column}"))?;
+    u32::from_str_radix(digits, 16).map_err(|_| format!("Invalid hex: {column}"))
+}
+
+/// Converts ISO-2022 style 2-byte code into kuten code.
+fn iso2022_to_kuten(code: u32) -> Result<(u8, u8), String> {
+    let (high, low) = ((code >> 8) as u8, code as u8);
+    let range = 0x21..=0x7E;
+    if code > 0xFFFF || !range.contains(&high) || !range.contains(&low) {
+        return Err(format!("Invalid JIS code: {code:#06X}"));
+    }
+    Ok((high - 0x20, low - 0x20))
+}
+
+/// Same as `picolony::string::sjis::sjis_to_kuten`.
+fn sjis_to_kuten(lead: u8, trail: u8) -> Option<(u8, u8)> {
+    let row_pair = match lead {
+        0x81..=0x9F => lead - 0x81,
+        0xE0..=0xEF => lead - 0xC1,
+        _ => return None,
+    };
+    match trail {
+        0x40..=0x7E => Some((row_pair * 2 + 1, trail - 0x3F)),
+        0x80..=0x9E => Some((row_pair * 2 + 1, trail - 0x40)),
+        0x9F..=0xFC => Some((row_pair * 2 + 2, trail - 0x9E)),
+        _ => None,
+    }
+}
+
+fn parse_jis(columns: &[&str]) -> Result<Option<Mapping>, String> {
+    let (jis, unicode) = match columns {
+        [] => return Ok(None),
+        [_, jis, unicode] | [jis, unicode] => (jis, unicode),
+        _ => return Err("Expected 2 or 3 columns".into()),
+    };
+    Ok(Some(Mapping {
+        codepoint: parse_hex(unicode, "0x")?,
+        kuten: iso2022_to_kuten(parse_hex(jis, "0x")?)?,
+    }))
+}
+
+fn parse_sjis(columns: &[&str]) -> Result<Option<Mapping>, String> {
+    // Undefined codes have no Unicode column.
+    let [sjis, unicode] = columns else {
+        return Ok(None);
+    };
+    let sjis = parse_hex(sjis, "0x")?;
+    if sjis < 0x100 {
+        return Ok(None);
+    }
+
+    // Vendor codes beyond 94 rows (e.g. IBM extension in 0xFA-0xFC) have no kuten code.
+    let Some(kuten) = sjis_to_kuten((sjis >> 8) as u8, sjis as u8) else {
+        return Ok(None);
+    };
+    Ok(Some(Mapping {
+        codepoint: parse_hex(unicode, "0x")?,
+        kuten,
+    }))
+}
+
+fn parse_jisx0213(columns: &[&str]) -> Result<Option<Mapping>, String> {
+    let [code, unicode, ..] = columns else {
+        return Ok(None);
+    };
+    let (plane, jis) = match code.split_at_checked(2) {
+        Some(("3-", jis)) => (1, jis),
+        Some(("4-", jis)) => (2, jis),
+        _ => return Err(format!("Invalid men-ku-ten: {code}")),
+    };
+
+    // Unassigned codes and combining sequences.
+    if !unicode.starts_with("U+") || unicode[2..].contains('+') {
+        return Ok(None);
+    }
+    let (ku, ten) = iso2022_to_kuten(u32::from_str_radix(jis, 16).map_err(|e| e.to_string())?)?;
+
+    // The other plane 2 rows are left for JIS X 0212, and must not be in JIS X 0213 mapping.
+    if plane == 2 && !matches!(ku, 1 | 3..=5 | 8 | 12..=15 | 78..=94) {
+        return Err(format!("Unassigned plane 2 row: {code}"));
+    }
+    Ok(Some(Mapping {
+        codepoint: parse_hex(unicode, "U+")?,
+        kuten: ((plane - 1) * KUTEN_WIDTH + ku, ten),
+    }))
+}
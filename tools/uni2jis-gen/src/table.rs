@@ -0,0 +1,177 @@
+//! Builds and reads table binaries.
+
+use crate::mapping::{Mapping, KUTEN_WIDTH};
+
+use std::collections::BTreeMap;
+
+/// Unicode to kuten mapping sorted by codepoint.
+pub type CodepointMap = BTreeMap<u32, (u8, u8)>;
+
+/// Which kuten code is taken when a codepoint appears more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    First,
+    Last,
+}
+
+/// Collects mappings into codepoint map.
+pub fn collect_codepoints(mappings: &[Mapping], duplicates: Duplicates) -> CodepointMap {
+    let mut codepoints = CodepointMap::new();
+    for mapping in mappings {
+        match duplicates {
+            Duplicates::First => {
+                codepoints.entry(mapping.codepoint).or_insert(mapping.kuten);
+            }
+            Duplicates::Last => {
+                codepoints.insert(mapping.codepoint, mapping.kuten);
+            }
+        }
+    }
+    codepoints
+}
+
+/// Maps ASCII characters to fullwidth glyphs in the way the shipped tables do.
+/// Controls and space map to ideographic space.
+/// ASCII mapped by input files is overwritten, since the fonts have no halfwidth glyphs.
+pub fn add_ascii(codepoints: &mut CodepointMap) {
+    for ascii in 0x00..0x80 {
+        let fullwidth = match ascii {
+            0x00..=0x20 | 0x7F => 0x3000,
+            0x22 => 0x201D,
+            0x27 => 0x2019,
+            0x5C => 0xFFE5,
+            _ => ascii + 0xFEE0,
+        };
+        if let Some(&kuten) = codepoints.get(&fullwidth) {
+            codepoints.insert(ascii, kuten);
+        }
+    }
+}
+
+/// Builds Unicode to kuten table binary in the format `Unicode2KutenTable::new` accepts.
+pub fn build_uni2kuten(
+    codepoints: &CodepointMap,
+    version: u16,
+    chain_length: u16,
+) -> Result<Vec<u8>, String> {
+    if !chain_length.is_power_of_two() {
+        return Err(format!("Chain length must be power of two: {chain_length}"));
+    }
+    let bmp: Vec<_> = codepoints.range(..0x10000).map(|(&c, &k)| (c, k)).collect();
+    let astral: Vec<_> = codepoints.range(0x10000..).map(|(&c, &k)| (c, k)).collect();
+    let bmp_count = u16::try_from(bmp.len()).map_err(|_| "Too many BMP elements")?;
+    let astral_count = u16::try_from(astral.len()).map_err(|_| "Too many astral elements")?;
+    if version == 1 && !astral.is_empty() {
+        return Err("Version 1 cannot contain characters outside BMP".into());
+    }
+
+    let mut bytes = vec![];
+    let mut push_words = |words: &[u16]| {
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+    };
+    let chains_count = 0x10000 / chain_length as u32 + (version == 3) as u32;
+    let chain_indices: Vec<_> = (0..chains_count)
+        .map(|chain| bmp.partition_point(|&(c, _)| c < chain * chain_length as u32) as u16)
+        .collect();
+    match version {
+        1 => push_words(&[chain_length, bmp_count]),
+        2 | 3 => push_words(&[0, version, chain_length, bmp_count, astral_count]),
+        _ => return Err(format!("Unsupported version: {version}")),
+    }
+    push_words(&chain_indices);
+
+    if version == 3 {
+        bytes.extend(bmp.iter().flat_map(|&(c, _)| (c as u16).to_le_bytes()));
+        bytes.extend(bmp.iter().flat_map(|&(_, (ku, ten))| [ku, ten]));
+    } else {
+        // The linear scan stops after one chain length of elements.
+        let longest = chain_indices
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .max()
+            .unwrap_or(0);
+        if longest > chain_length {
+            return Err(format!("Chain overflows: {longest} elements"));
+        }
+        for &(c, (ku, ten)) in &bmp {
+            bytes.extend_from_slice(&(c as u16).to_le_bytes());
+            bytes.extend_from_slice(&[ku, ten]);
+        }
+    }
+    for &(c, (ku, ten)) in &astral {
+        bytes.extend_from_slice(&c.to_le_bytes()[..3]);
+        bytes.extend_from_slice(&[ku, ten]);
+    }
+    Ok(bytes)
+}
+
+/// Reads Unicode to kuten table binary of any version.
+pub fn read_uni2kuten(bytes: &[u8]) -> Result<CodepointMap, String> {
+    let word = |i: usize| -> Result<usize, String> {
+        let pair = bytes.get(i..(i + 2)).ok_or("Unexpected end of table")?;
+        Ok(u16::from_le_bytes([pair[0], pair[1]]) as usize)
+    };
+    let (header_size, version, chain_length, bmp_count, astral_count) = match word(0)? {
+        0 => (10, word(2)?, word(4)?, word(6)?, word(8)?),
+        chain_length => (4, 1, chain_length, word(2)?, 0),
+    };
+    if !(1..=3).contains(&version) || !chain_length.is_power_of_two() {
+        return Err(format!(
+            "Unsupported header: version {version}, chain {chain_length}"
+        ));
+    }
+
+    let chains_count = 0x10000 / chain_length + (version == 3) as usize;
+    let elements_start = header_size + chains_count * 2;
+    let astral_start = elements_start + bmp_count * 4;
+    if bytes.len() != astral_start + astral_count * 5 {
+        return Err("Table size does not match header".into());
+    }
+
+    let mut codepoints = CodepointMap::new();
+    for i in 0..bmp_count {
+        let (c, kuten) = if version == 3 {
+            let kuten_index = elements_start + (bmp_count + i) * 2;
+            (
+                word(elements_start + i * 2)?,
+                (bytes[kuten_index], bytes[kuten_index + 1]),
+            )
+        } else {
+            let element = elements_start + i * 4;
+            (word(element)?, (bytes[element + 2], bytes[element + 3]))
+        };
+        codepoints.insert(c as u32, kuten);
+    }
+    for element in bytes[astral_start..].chunks_exact(5) {
+        let c = u32::from_le_bytes([element[0], element[1], element[2], 0]);
+        codepoints.insert(c, (element[3], element[4]));
+    }
+    Ok(codepoints)
+}
+
+/// Builds dense kuten to Unicode table binary of single plane.
+/// Cells are filled only if the codepoint maps back to them in `codepoints`,
+/// so that both tables follow the same duplicates policy and round trip.
+/// The first codepoint wins for each kuten code.
+pub fn build_kuten2uni(mappings: &[Mapping], codepoints: &CodepointMap) -> Result<Vec<u8>, String> {
+    let width = KUTEN_WIDTH as usize;
+    let mut bytes = vec![0; width * width * 3];
+    for mapping in mappings {
+        let (ku, ten) = mapping.kuten;
+        if ku > KUTEN_WIDTH {
+            return Err(format!(
+                "Kuten to Unicode table has single plane: {ku}-{ten}"
+            ));
+        }
+        if codepoints.get(&mapping.codepoint) != Some(&mapping.kuten) {
+            continue;
+        }
+        let index = ((ku as usize - 1) * width + (ten as usize - 1)) * 3;
+        if bytes[index..(index + 3)] == [0; 3] {
+            bytes[index..(index + 3)].copy_from_slice(&mapping.codepoint.to_le_bytes()[..3]);
+        }
+    }
+    Ok(bytes)
+}